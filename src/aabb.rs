@@ -0,0 +1,100 @@
+use crate::{
+    Double,
+    interval::Interval,
+    ray::Ray,
+    vec3::{Point3, Vector3},
+};
+
+/// Axis-aligned bounding box, one [`Interval`] per axis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb {
+        x: Interval::EMPTY,
+        y: Interval::EMPTY,
+        z: Interval::EMPTY,
+    };
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }.pad_to_minimums()
+    }
+    /// Treat the two points `a` and `b` as extrema for the bounding box,
+    /// so we don't require a particular minimum/maximum coordinate order.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        let [x, y, z] = [0, 1, 2].map(|n| Interval::new(a[n].min(b[n]), a[n].max(b[n])));
+        Self::new(x, y, z)
+    }
+    /// The box enclosing both `a` and `b`.
+    pub fn enclosing(a: Aabb, b: Aabb) -> Self {
+        Self {
+            x: Interval::enclosing(a.x, b.x),
+            y: Interval::enclosing(a.y, b.y),
+            z: Interval::enclosing(a.z, b.z),
+        }
+    }
+    pub fn axis_interval(&self, n: usize) -> Interval {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
+    /// Returns the index of the longest axis of the bounding box.
+    pub fn longest_axis(&self) -> usize {
+        let sizes = [self.x.size(), self.y.size(), self.z.size()];
+        if sizes[0] > sizes[1] {
+            if sizes[0] > sizes[2] { 0 } else { 2 }
+        } else if sizes[1] > sizes[2] {
+            1
+        } else {
+            2
+        }
+    }
+    pub fn centroid(&self) -> Point3 {
+        Point3::new([0, 1, 2].map(|n| {
+            let interval = self.axis_interval(n);
+            (interval.min + interval.max) * 0.5
+        }))
+    }
+    pub fn surface_area(&self) -> Double {
+        let [dx, dy, dz] = [self.x.size(), self.y.size(), self.z.size()];
+        if dx < 0.0 || dy < 0.0 || dz < 0.0 {
+            return 0.0;
+        }
+        2.0 * (dx * dy + dy * dz + dz * dx)
+    }
+    /// Slab test: the ray hits the box if the `t` ranges where it is
+    /// between the two planes of each axis overlap.
+    pub fn hit(&self, ray: &Ray, mut ray_t_range: Interval) -> bool {
+        let origin = ray.origin;
+        let direction: Vector3 = ray.direction;
+        for axis in 0..3 {
+            let interval = self.axis_interval(axis);
+            let inverse = 1.0 / direction[axis];
+            let t0 = (interval.min - origin[axis]) * inverse;
+            let t1 = (interval.max - origin[axis]) * inverse;
+            let (t_near, t_far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            ray_t_range.min = ray_t_range.min.max(t_near);
+            ray_t_range.max = ray_t_range.max.min(t_far);
+            if ray_t_range.max <= ray_t_range.min {
+                return false;
+            }
+        }
+        true
+    }
+    /// Adjust the AABB so that no side is narrower than some delta,
+    /// flat primitives would otherwise give a box without volume.
+    fn pad_to_minimums(mut self) -> Self {
+        let delta = 0.0001;
+        for interval in [&mut self.x, &mut self.y, &mut self.z] {
+            if interval.size() < delta {
+                *interval = interval.expand(delta);
+            }
+        }
+        self
+    }
+}
@@ -1,22 +1,60 @@
 use crate::{
     AsDouble, Double,
-    color::RGB,
-    hittable::Hittable,
-    interval::Interval,
+    color::{RGB, ToneMap},
+    environment::{Environment, Sky},
+    filter::{Filter, PixelSum},
+    hittable::{Hittable, HittableList},
+    image::Image,
+    integrator::{Integrator, Scene, path::PathTracer},
+    light::LightList,
     ray::Ray,
+    sampler::{Sampler, SamplerKind},
     vec3::{Point3, Vector3},
 };
-use std::fmt::Write as _;
-use std::fs;
-use std::io::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 pub struct Camera {
     pub aspect_ratio: Double,
     pub image_width: u32,
+    /// Count of random samples for each pixel
+    pub samples_per_pixel: u32,
+    /// Reconstruction filter weighting the samples of a pixel
+    pub filter: Filter,
+    /// Computes the radiance along each camera ray
+    pub integrator: Box<dyn Integrator>,
+    /// Radiance of rays which leave the scene, a black background leaves only emitters lighting it
+    pub background: Box<dyn Environment>,
+    /// Vertical view angle (field of view), in degrees
+    pub vfov: Double,
+    /// Point camera is looking from
+    pub look_from: Point3,
+    /// Point camera is looking at
+    pub look_at: Point3,
+    /// Camera-relative "up" direction
+    pub vup: Vector3,
+    /// Variation angle of rays through each pixel, in degrees
+    pub defocus_angle: Double,
+    /// Distance from camera look_from point to plane of perfect focus
+    pub focus_dist: Double,
+    /// Exposure compensation in stops (EV) applied when the image is written
+    pub exposure: Double,
+    /// Operator compressing the radiance into displayable values
+    pub tone_map: ToneMap,
+    /// Generator of the sample values for the film, lens and BSDF
+    pub sampler: SamplerKind,
+    /// Global seed of the random numbers, renders with the same seed are identical
+    pub seed: u64,
+    /// Number of worker threads, 0 uses all available cores
+    pub threads: usize,
+    /// Edge length of the square tiles handed out to workers, in pixels
+    pub tile_size: u32,
     image_height: u32,
     origin: Point3,
     start_pixel: Point3,
     pixel_offset: Offset,
+    /// Defocus disk horizontal and vertical radius
+    defocus_disk: Offset,
 }
 
 impl Default for Camera {
@@ -24,16 +62,41 @@ impl Default for Camera {
         Self {
             aspect_ratio: 1.0,
             image_width: 100,
+            samples_per_pixel: 1,
+            filter: Default::default(),
+            integrator: Box::new(PathTracer::default()),
+            background: Box::new(Sky),
+            vfov: 90.0,
+            look_from: Point3::default(),
+            look_at: Point3::default().with_z(-1.0),
+            vup: Vector3::default().with_y(1.0),
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            exposure: 0.0,
+            tone_map: Default::default(),
+            sampler: Default::default(),
+            seed: 0,
+            threads: 0,
+            tile_size: 16,
             image_height: Default::default(),
             origin: Default::default(),
             start_pixel: Default::default(),
             pixel_offset: Default::default(),
+            defocus_disk: Default::default(),
         }
     }
 }
 
 impl Camera {
     pub fn new(aspect_ratio: Double, image_width: u32) -> Self {
+        Self {
+            aspect_ratio,
+            image_width,
+            ..Default::default()
+        }
+    }
+    /// Compute the image height and the viewport from the public parameters.
+    fn initialize(&mut self) {
         struct Viewport {
             upper_left: Point3,
             horizontal: Vector3,
@@ -41,22 +104,27 @@ impl Camera {
         }
         // image
         // w/h=16/9
-        let image_height = (image_width.as_double() / aspect_ratio) as u32;
+        let image_height = (self.image_width.as_double() / self.aspect_ratio) as u32;
         let image_height = if image_height < 1 { 1 } else { image_height };
         // camera
         // use right-handed coordinate system
-        // y-axis go up, the x-axis to the right,
-        // and the negative z-axis pointing in the viewing direction
-        let focal_len = 1.0;
-        let camera_origin = Point3::default();
-        let camera_direction = Vector3::default().with_z(-focal_len);
+        // the camera looks down its -w axis, v is up and u points to the right
+        let camera_origin = self.look_from;
+        // u,v,w unit basis vectors for the camera coordinate frame
+        let w = (self.look_from - self.look_at).unit_vector();
+        let u = self.vup.cross(w).unit_vector();
+        let v = w.cross(u);
         let viewport = {
-            let height = 2.0;
-            let ratio = image_width.as_double() / image_height.as_double();
+            // the viewport lies on the plane of perfect focus
+            // tan(θ/2) = (height/2) / focus_dist
+            let theta = self.vfov.to_radians();
+            let height = 2.0 * (theta / 2.0).tan() * self.focus_dist;
+            let ratio = self.image_width.as_double() / image_height.as_double();
             let width = height * ratio;
-            let horizontal = Vector3::default().with_x(width);
-            let vertical = Vector3::default().with_y(-height);
-            let center = camera_origin + camera_direction;
+            // across the horizontal and down the vertical viewport edges
+            let horizontal = u * width;
+            let vertical = -v * height;
+            let center = camera_origin + (-w) * self.focus_dist;
             let upper_left = center + ((-horizontal) + (-vertical)) * 0.5;
             Viewport {
                 upper_left,
@@ -65,74 +133,433 @@ impl Camera {
             }
         };
         let pixel_offset = Offset {
-            horizontal: viewport.horizontal / image_width.as_double(),
+            horizontal: viewport.horizontal / self.image_width.as_double(),
             vertical: viewport.vertical / image_height.as_double(),
         };
         let start_pixel =
             viewport.upper_left + (pixel_offset.horizontal + pixel_offset.vertical) * 0.5;
-        Self {
-            aspect_ratio,
-            image_width,
-            image_height,
-            origin: camera_origin,
-            start_pixel,
-            pixel_offset,
+        self.image_height = image_height;
+        self.origin = camera_origin;
+        self.start_pixel = start_pixel;
+        self.pixel_offset = pixel_offset;
+        // the lens is a disk centered at look_from, its radius grows with the focus distance
+        let defocus_radius = self.focus_dist * (self.defocus_angle / 2.0).to_radians().tan();
+        self.defocus_disk = Offset {
+            horizontal: u * defocus_radius,
+            vertical: v * defocus_radius,
+        };
+    }
+    pub fn with_vfov(mut self, vfov: Double) -> Self {
+        self.vfov = vfov;
+        self
+    }
+    pub fn with_look_from(mut self, look_from: Point3) -> Self {
+        self.look_from = look_from;
+        self
+    }
+    pub fn with_look_at(mut self, look_at: Point3) -> Self {
+        self.look_at = look_at;
+        self
+    }
+    pub fn with_vup(mut self, vup: Vector3) -> Self {
+        self.vup = vup;
+        self
+    }
+    pub fn with_defocus_angle(mut self, defocus_angle: Double) -> Self {
+        self.defocus_angle = defocus_angle;
+        self
+    }
+    pub fn with_focus_dist(mut self, focus_dist: Double) -> Self {
+        self.focus_dist = focus_dist;
+        self
+    }
+    pub fn with_samples_per_pixel(mut self, samples_per_pixel: u32) -> Self {
+        self.samples_per_pixel = samples_per_pixel.max(1);
+        self
+    }
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+    pub fn with_integrator<I: Integrator + 'static>(mut self, integrator: I) -> Self {
+        self.integrator = Box::new(integrator);
+        self
+    }
+    /// A [`RGB`] color gives the same radiance in every direction.
+    pub fn with_background<E: Environment + 'static>(mut self, background: E) -> Self {
+        self.background = Box::new(background);
+        self
+    }
+    pub fn with_exposure(mut self, exposure: Double) -> Self {
+        self.exposure = exposure;
+        self
+    }
+    pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.tone_map = tone_map;
+        self
+    }
+    pub fn with_sampler(mut self, sampler: SamplerKind) -> Self {
+        self.sampler = sampler;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+    /// Render `world` into a framebuffer of linear colors.
+    pub fn render(self, world: impl Hittable) -> Image {
+        self.render_with_lights(world, HittableList::new())
+    }
+    /// Render `world`, sampling the emitters in `lights` directly.
+    ///
+    /// `lights` holds copies of the emitting objects of `world`, only their shape is used.
+    pub fn render_with_lights(self, world: impl Hittable, lights: impl Hittable) -> Image {
+        self.render_scene(world, lights, LightList::new())
+    }
+    /// Render `world` lit by the area emitters in `lights` and by the lights of `light_list`.
+    pub fn render_scene(
+        mut self,
+        world: impl Hittable,
+        lights: impl Hittable,
+        light_list: LightList,
+    ) -> Image {
+        self.initialize();
+        let scene = Scene {
+            world: &world,
+            lights: &lights,
+            light_list: &light_list,
+            background: self.background.as_ref(),
+        };
+        let pixels = self.render_pixels(&scene);
+        Image::from_pixels(self.image_width, self.image_height, pixels)
+            .with_exposure(self.exposure)
+            .with_tone_map(self.tone_map)
+    }
+    /// Render every pixel on a pool of scoped worker threads.
+    ///
+    /// Workers pull tiles from a shared queue until it is empty, so a slow
+    /// tile does not hold up the others. The random generator is seeded per
+    /// sample, the result does not depend on the thread count.
+    fn render_pixels(&self, scene: &Scene) -> Vec<RGB> {
+        let (width, height) = (self.image_width, self.image_height);
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
+        let workers = self.thread_count().min(tiles.len()).max(1);
+
+        let mut pixels = vec![RGB::default(); width as usize * height as usize];
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut finished = Vec::new();
+                        while let Some(tile) = tiles.get(next_tile.fetch_add(1, Ordering::Relaxed))
+                        {
+                            finished.push((*tile, self.render_tile(tile, scene)));
+                        }
+                        finished
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let finished = handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err));
+                for (tile, colors) in finished {
+                    let tile_width = (tile.x1 - tile.x0) as usize;
+                    for (row, j) in (tile.y0..tile.y1).enumerate() {
+                        let start = j as usize * width as usize + tile.x0 as usize;
+                        pixels[start..start + tile_width]
+                            .copy_from_slice(&colors[row * tile_width..(row + 1) * tile_width]);
+                    }
+                }
+            }
+        });
+        pixels
+    }
+    /// Split the image into `tile_size` squares, the last row and column may be smaller.
+    fn tiles(&self) -> Vec<Tile> {
+        let size = self.tile_size.max(1);
+        let mut tiles = Vec::new();
+        for y0 in (0..self.image_height).step_by(size as usize) {
+            for x0 in (0..self.image_width).step_by(size as usize) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + size).min(self.image_width),
+                    y1: (y0 + size).min(self.image_height),
+                });
+            }
         }
+        tiles
     }
-    pub fn render(self, world: impl Hittable) {
-        let Camera {
-            image_width,
-            image_height,
-            origin,
-            start_pixel,
-            pixel_offset,
-            ..
-        } = self;
-        let mut buf = String::new();
-        let _ = write!(buf, "P3\n{} {}\n255\n", image_width, image_height);
-
-        for j in 0..image_height {
-            for i in 0..image_width {
-                let pixel_center = start_pixel
-                    + pixel_offset.horizontal * i.as_double()
-                    + pixel_offset.vertical * j.as_double();
-
-                let ray_direction = pixel_center - origin;
-                let ray = Ray::new(origin, ray_direction);
-                ray_color(&ray, &world).write_color(&mut buf);
+    fn thread_count(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        }
+    }
+    fn render_tile(&self, tile: &Tile, scene: &Scene) -> Vec<RGB> {
+        let mut sampler = self.sampler.create(self.seed, self.samples_per_pixel);
+        let mut colors = Vec::with_capacity(((tile.x1 - tile.x0) * (tile.y1 - tile.y0)) as usize);
+        for j in tile.y0..tile.y1 {
+            for i in tile.x0..tile.x1 {
+                colors.push(self.render_pixel(i, j, scene, sampler.as_mut()));
             }
         }
-        match fs::File::create("img.ppm") {
-            Ok(mut file) => {
-                let _ = file.write_all(buf.as_bytes());
+        colors
+    }
+    fn render_pixel(&self, i: u32, j: u32, scene: &Scene, sampler: &mut dyn Sampler) -> RGB {
+        let samples_per_pixel = self.samples_per_pixel;
+        let filter = self.filter;
+        let radius = filter.radius();
+        let pixel_index = j as u64 * self.image_width as u64 + i as u64;
+        let mut pixel = PixelSum::default();
+        for sample_index in 0..samples_per_pixel {
+            sampler.start_pixel_sample(pixel_index, sample_index as u64);
+            // a single sample stays at the pixel center
+            let (dx, dy) = if samples_per_pixel == 1 {
+                (0.0, 0.0)
+            } else {
+                let [u, v] = sampler.get_2d();
+                ((2.0 * u - 1.0) * radius, (2.0 * v - 1.0) * radius)
+            };
+            let weight = filter.evaluate(dx, dy);
+            if weight == 0.0 {
+                continue;
             }
-            Err(err) => println!("{:?}", err),
+            let ray = self.get_ray(i, j, dx, dy, sampler);
+            pixel.add(self.integrator.ray_color(&ray, scene, sampler), weight);
         }
+        pixel.resolve()
+    }
+    /// Construct a camera ray through pixel (i, j), displaced by (dx, dy) pixels from its center.
+    /// The ray originates from a random point on the defocus disk.
+    fn get_ray(&self, i: u32, j: u32, dx: Double, dy: Double, sampler: &mut dyn Sampler) -> Ray {
+        let Offset {
+            horizontal,
+            vertical,
+        } = self.pixel_offset;
+        let pixel_sample =
+            self.start_pixel + horizontal * (i.as_double() + dx) + vertical * (j.as_double() + dy);
+        let ray_origin = if self.defocus_angle <= 0.0 {
+            self.origin
+        } else {
+            self.defocus_disk_sample(sampler)
+        };
+        Ray::new(ray_origin, pixel_sample - ray_origin)
+    }
+    /// Returns a random point in the camera defocus disk.
+    fn defocus_disk_sample(&self, sampler: &mut dyn Sampler) -> Point3 {
+        let [x, y, _] = Vector3::random_in_unit_disk(sampler).0;
+        self.origin + self.defocus_disk.horizontal * x + self.defocus_disk.vertical * y
     }
 }
 
+/// Pixel rectangle `[x0, x1) x [y0, y1)` rendered by one worker.
+#[derive(Clone, Copy)]
+struct Tile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
 #[derive(Default, Clone, Copy)]
 struct Offset {
     horizontal: Vector3,
     vertical: Vector3,
 }
-fn ray_color(ray: &Ray, world: &impl Hittable) -> RGB {
-    if let Some(record) = world.hit(ray, Interval::new(0.0, f64::INFINITY)) {
-        // world color
-        // visualizing normal 可视化法向量
-        // normal.xyz() ∈ [-1, 1], + 1 → ∈ [0, 2],* 0.5 → ∈ [0, 1]
-        // map normal.xyz => rgb
-        let rgb = record.normal.map(|n| (n + 1.0) * 0.5);
-        return RGB::new(rgb);
-    }
-
-    // unit_vector.y() ∈ [-1, 1], + 1 → ∈ [0, 2],* 0.5 → ∈ [0, 1]
-    let unit_vector = ray.direction.unit_vector();
-    //interpolation factor
-    let factor = (unit_vector.y() + 1.0) * 0.5;
-    // background color
-    let white = RGB::new([1.0; 3]);
-    let blue = RGB::new([0.5, 0.7, 1.0]);
-    // linear blend / lerp
-    white * (1.0 - factor) + blue * factor
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::independent::IndependentSampler;
+
+    /// Ray through the point `(x, y)` of the image, in pixels from its top left corner.
+    fn ray_through(camera: &Camera, x: Double, y: Double, sampler: &mut dyn Sampler) -> Ray {
+        camera.get_ray(0, 0, x - 0.5, y - 0.5, sampler)
+    }
+
+    /// Angle between `a` and `b`, in degrees.
+    fn angle(a: Vector3, b: Vector3) -> Double {
+        a.unit_vector()
+            .dot(b.unit_vector())
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees()
+    }
+
+    #[test]
+    fn basis_follows_look_at_and_vup() {
+        // looking along +X with +Y up, so +Z is to the right
+        let mut camera = Camera::new(2.0, 200)
+            .with_look_from(Point3::new([1.0, 2.0, 3.0]))
+            .with_look_at(Point3::new([5.0, 2.0, 3.0]))
+            .with_vup(Vector3::new([0.0, 3.0, 0.0]))
+            .with_vfov(60.0);
+        camera.initialize();
+        assert_eq!(camera.image_height, 100);
+        let mut sampler = IndependentSampler::new(1);
+        let center = ray_through(&camera, 100.0, 50.0, &mut sampler);
+        assert_eq!(center.origin, camera.look_from);
+        assert!(angle(center.direction, Vector3::new([1.0, 0.0, 0.0])) < 1e-6);
+        // the edges of the image are half the field of view away, up and right
+        let top = ray_through(&camera, 100.0, 0.0, &mut sampler).direction;
+        assert!((angle(top, center.direction) - 30.0).abs() < 1e-9);
+        assert!(top.y() > 0.0 && top.z().abs() < 1e-12);
+        let right = ray_through(&camera, 200.0, 50.0, &mut sampler).direction;
+        // twice as wide as high
+        let horizontal_fov = (2.0 * 30.0_f64.to_radians().tan()).atan().to_degrees();
+        assert!((angle(right, center.direction) - horizontal_fov).abs() < 1e-9);
+        assert!(right.z() > 0.0 && right.y().abs() < 1e-12);
+    }
+
+    #[test]
+    fn vfov_sets_the_vertical_angle() {
+        for vfov in [10.0, 45.0, 90.0, 120.0] {
+            let mut camera = Camera::new(1.0, 10).with_vfov(vfov);
+            camera.initialize();
+            let mut sampler = IndependentSampler::new(1);
+            let top = ray_through(&camera, 5.0, 0.0, &mut sampler).direction;
+            let bottom = ray_through(&camera, 5.0, 10.0, &mut sampler).direction;
+            assert!((angle(top, bottom) - vfov).abs() < 1e-9, "{vfov}");
+        }
+    }
+
+    #[test]
+    fn no_defocus_is_a_pinhole() {
+        let look_from = Point3::new([0.5, 1.0, 2.0]);
+        let mut sampler = IndependentSampler::new(3);
+        let mut directions = Vec::new();
+        for focus_dist in [1.0, 3.5] {
+            let mut camera = Camera::new(1.5, 30)
+                .with_look_from(look_from)
+                .with_look_at(Point3::new([0.0, 0.5, -1.0]))
+                .with_defocus_angle(0.0)
+                .with_focus_dist(focus_dist);
+            camera.initialize();
+            for (x, y) in [(0.0, 0.0), (12.3, 7.5), (30.0, 20.0)] {
+                let ray = ray_through(&camera, x, y, &mut sampler);
+                assert_eq!(ray.origin, look_from);
+                directions.push(ray.direction.unit_vector());
+            }
+        }
+        // the focus distance only moves the viewport along the same rays
+        let (near, far) = directions.split_at(3);
+        for (a, b) in near.iter().zip(far) {
+            assert!((*a - *b).len() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn defocused_rays_meet_on_the_focus_plane() {
+        let look_from = Point3::new([0.5, 1.0, 2.0]);
+        let build = |defocus_angle| {
+            let mut camera = Camera::new(1.5, 30)
+                .with_look_from(look_from)
+                .with_look_at(Point3::new([0.0, 0.5, -1.0]))
+                .with_defocus_angle(defocus_angle)
+                .with_focus_dist(4.0);
+            camera.initialize();
+            camera
+        };
+        let (pinhole, lens) = (build(0.0), build(10.0));
+        let forward = (pinhole.look_at - look_from).unit_vector();
+        let radius = 4.0 * 5.0_f64.to_radians().tan();
+        let mut sampler = IndependentSampler::new(3);
+        let mut widest: Double = 0.0;
+        for (x, y) in [(15.0, 10.0), (2.0, 18.5)] {
+            let target = ray_through(&pinhole, x, y, &mut sampler).at(1.0);
+            for _ in 0..1000 {
+                let ray = ray_through(&lens, x, y, &mut sampler);
+                // every ray starts on the lens and passes through the pinhole's focus point
+                let offset = ray.origin - look_from;
+                assert!(offset.dot(forward).abs() < 1e-12);
+                assert!(offset.len() <= radius + 1e-12);
+                widest = widest.max(offset.len());
+                assert!((ray.at(1.0) - target).len() < 1e-12);
+            }
+        }
+        // and the whole lens is used
+        assert!(widest > 0.95 * radius);
+    }
+
+    #[test]
+    fn constant_radiance_is_kept_by_every_filter() {
+        let background = RGB::new([0.5, 0.25, 2.0]);
+        let filters = [
+            Filter::Box,
+            Filter::Tent,
+            Filter::gaussian(),
+            Filter::mitchell(),
+        ];
+        for filter in filters {
+            for samples_per_pixel in [1, 2, 3, 16] {
+                let image = Camera::new(1.0, 8)
+                    .with_samples_per_pixel(samples_per_pixel)
+                    .with_filter(filter)
+                    .with_background(background)
+                    .render(HittableList::new());
+                for pixel in image.pixels() {
+                    let error = (*pixel - background).0.map(f64::abs);
+                    assert!(
+                        error.iter().all(|&e| e < 1e-9),
+                        "{filter:?} at {samples_per_pixel} spp gave {pixel:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn output_does_not_depend_on_thread_count() {
+        use crate::hittable::sphere::Sphere;
+        use crate::material::lambertian::Lambertian;
+        use crate::material::metal::Metal;
+
+        let render = |sampler: SamplerKind, threads: usize| {
+            let mut world = HittableList::new();
+            world.push(
+                Sphere::from_array([0.0, 0.0, -1.0])
+                    .with_radius(0.5)
+                    .with_material(Lambertian::new(RGB::new([0.5; 3]))),
+            );
+            world.push(
+                Sphere::from_array([0.0, -100.5, -1.0])
+                    .with_radius(100.0)
+                    .with_material(Metal::new(RGB::new([0.8; 3]), 0.3)),
+            );
+            Camera::new(1.5, 24)
+                .with_samples_per_pixel(4)
+                .with_sampler(sampler)
+                .with_tile_size(5)
+                .with_threads(threads)
+                .render(world)
+        };
+        let samplers = [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ];
+        for sampler in samplers {
+            let single = render(sampler, 1);
+            for threads in [2, 3, 8] {
+                assert_eq!(
+                    single.pixels(),
+                    render(sampler, threads).pixels(),
+                    "{sampler:?} with {threads} threads"
+                );
+            }
+        }
+    }
 }
@@ -1,20 +1,188 @@
 use std::fmt::Write as _;
 
 use crate::{Array3, Double};
+use crate::interval::Interval;
 use crate::vec3::Vec3;
 use crate::{gen_getter, vec3_op_scalar_and_op_assign, vec3_op_vec3_and_op_assign};
 
 pub type RGB = Vec3<Color>;
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Default)]
 pub struct Color;
 impl RGB {
-    pub fn write_color(&self, buf: &mut String) {
+    pub fn write_color(&self, buf: &mut String, display: DisplayTransform) {
+        let [r, g, b] = self.to_bytes(display);
+        let _ = writeln!(buf, "{} {} {}", r, g, b);
+    }
+    /// Relative luminance of the linear color (Rec. 709 primaries).
+    pub fn luminance(&self) -> Double {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+    /// Run the linear color through `display` and clamp it to [0,1].
+    pub fn encode(&self, display: DisplayTransform) -> RGB {
+        let intensity = Interval::new(0.0, 1.0);
+        let exposed = *self * 2f64.powf(display.exposure);
+        let mapped = display.tone_map.apply(exposed);
+        RGB::new(
+            mapped
+                .0
+                .map(|n| intensity.clamp(display.transfer.encode(n))),
+        )
+    }
+    /// Encode and quantize the color to 8 bits per channel.
+    pub fn to_bytes(&self, display: DisplayTransform) -> [u8; 3] {
         fn translate(old: Array3) -> [u8; 3] {
             let scalar = 255.999;
             old.map(|n| (scalar * n) as u8)
         }
-        let [r, g, b] = translate(self.0);
-        let _ = writeln!(buf, "{} {} {}", r, g, b);
+        translate(self.encode(display).0)
+    }
+    /// Encode and quantize the color to 16 bits per channel.
+    pub fn to_words(&self, display: DisplayTransform) -> [u16; 3] {
+        let scalar = 65535.999;
+        self.encode(display).0.map(|n| (scalar * n) as u16)
+    }
+}
+
+/// Turns linear radiance into display values: exposure, tone mapping, then the transfer function.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DisplayTransform {
+    /// Exposure compensation in stops (EV), the radiance is scaled by `2^exposure`
+    pub exposure: Double,
+    pub tone_map: ToneMap,
+    pub transfer: TransferFunction,
+}
+
+/// Compresses high dynamic range radiance into [0,1].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ToneMap {
+    /// Leave the radiance alone, values above 1 are clipped
+    #[default]
+    Clamp,
+    /// `L / (1 + L)` on the luminance
+    Reinhard,
+    /// Reinhard which maps the luminance `white` to 1 instead of infinity,
+    /// a `white` which is not positive is taken as infinite
+    ExtendedReinhard { white: Double },
+    /// John Hable's filmic curve from Uncharted 2
+    Hable,
+    /// Stephen Hill's fit of the ACES reference rendering and output transforms
+    Aces,
+    /// Troy Sobotka's AgX, after Benjamin Wrensch's minimal polynomial fit
+    AgX,
+}
+
+impl ToneMap {
+    pub fn apply(&self, color: RGB) -> RGB {
+        match *self {
+            ToneMap::Clamp => color,
+            ToneMap::ExtendedReinhard { white } if white > 0.0 => {
+                scale_luminance(color, |l| l * (1.0 + l / (white * white)) / (1.0 + l))
+            }
+            ToneMap::Reinhard | ToneMap::ExtendedReinhard { .. } => {
+                scale_luminance(color, |l| l / (1.0 + l))
+            }
+            ToneMap::Hable => {
+                fn partial(x: Double) -> Double {
+                    let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+                    ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f
+                }
+                let exposure_bias = 2.0;
+                let white_scale = 1.0 / partial(11.2);
+                RGB::new(color.0.map(|n| partial(n * exposure_bias) * white_scale))
+            }
+            ToneMap::Aces => {
+                const INPUT: [Array3; 3] = [
+                    [0.59719, 0.35458, 0.04823],
+                    [0.07600, 0.90834, 0.01566],
+                    [0.02840, 0.13383, 0.83777],
+                ];
+                const OUTPUT: [Array3; 3] = [
+                    [1.60475, -0.53108, -0.07367],
+                    [-0.10208, 1.10813, -0.00605],
+                    [-0.00327, -0.07276, 1.07602],
+                ];
+                let v = mat3_mul(&INPUT, color.0).map(|v| {
+                    let a = v * (v + 0.0245786) - 0.000090537;
+                    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+                    a / b
+                });
+                RGB::new(mat3_mul(&OUTPUT, v))
+            }
+            ToneMap::AgX => {
+                const INPUT: [Array3; 3] = [
+                    [0.842479062253094, 0.0784335999999992, 0.0792237451477643],
+                    [0.0423282422610123, 0.878468636469772, 0.0791661274605434],
+                    [0.0423756549057051, 0.0784336, 0.879142973793104],
+                ];
+                const OUTPUT: [Array3; 3] = [
+                    [1.19687900512017, -0.0980208811401368, -0.0990297440797205],
+                    [-0.0528968517574562, 1.15190312990417, -0.0989611768448433],
+                    [-0.0529716355144438, -0.0980434501171241, 1.15107367264116],
+                ];
+                let (min_ev, max_ev) = (-12.47393, 4.026069);
+                let v = mat3_mul(&INPUT, color.0).map(|v| {
+                    // log2 encoding into [0,1], then the sigmoid contrast curve
+                    let x =
+                        (v.max(1e-10).log2().clamp(min_ev, max_ev) - min_ev) / (max_ev - min_ev);
+                    let x2 = x * x;
+                    let x4 = x2 * x2;
+                    15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x
+                        + 0.4298 * x2
+                        + 0.1191 * x
+                        - 0.00232
+                });
+                // the curve outputs display values, undo a 2.2 gamma to stay linear
+                RGB::new(mat3_mul(&OUTPUT, v).map(|n| n.max(0.0).powf(2.2)))
+            }
+        }
+    }
+}
+
+/// Scale the color so its luminance becomes `map(luminance)`, keeping the hue.
+fn scale_luminance(color: RGB, map: impl Fn(Double) -> Double) -> RGB {
+    let luminance = color.luminance();
+    if luminance <= 0.0 {
+        return RGB::default();
+    }
+    color * (map(luminance) / luminance)
+}
+
+fn mat3_mul(m: &[Array3; 3], v: Array3) -> Array3 {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// Maps linear intensity to the non-linear values stored by 8 and 16 bit formats.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TransferFunction {
+    /// The piecewise sRGB curve, what image viewers expect
+    #[default]
+    Srgb,
+    /// Pure power law `x^(1/gamma)`, e.g. gamma 2.2; a gamma which is not positive
+    /// leaves the values linear
+    Gamma(Double),
+    /// Store the linear values unchanged
+    Linear,
+}
+
+impl TransferFunction {
+    pub fn encode(&self, linear: Double) -> Double {
+        match *self {
+            TransferFunction::Srgb => {
+                if linear <= 0.0031308 {
+                    12.92 * linear
+                } else {
+                    1.055 * linear.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            TransferFunction::Gamma(gamma) if gamma > 0.0 => {
+                if linear > 0.0 {
+                    linear.powf(1.0 / gamma)
+                } else {
+                    0.0
+                }
+            }
+            TransferFunction::Gamma(_) | TransferFunction::Linear => linear,
+        }
     }
 }
 gen_getter! {RGB[r,g,b]=>Double}
@@ -33,3 +201,79 @@ vec3_op_scalar_and_op_assign! {
     [add_assign,sub_assign,mul_assign,div_assign]
     RGB,Double =>RGB
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATORS: [ToneMap; 6] = [
+        ToneMap::Clamp,
+        ToneMap::Reinhard,
+        ToneMap::ExtendedReinhard { white: 4.0 },
+        ToneMap::Hable,
+        ToneMap::Aces,
+        ToneMap::AgX,
+    ];
+
+    #[test]
+    fn tone_maps_are_monotone_from_black() {
+        for operator in OPERATORS {
+            let black = operator.apply(RGB::default());
+            let black_enough = black.0.iter().all(|n| n.abs() < 1e-3);
+            assert!(black_enough, "{operator:?} maps 0 to {black:?}");
+            // grey and a saturated color, from black to bright; AgX clips each channel
+            // beyond 2^4, where the mix back to RGB bends the channels of a saturated color
+            for (hue, brightest) in [([1.0; 3], 1e4), ([1.0, 0.4, 0.1], 16.0)] {
+                let mut previous = black;
+                let mut scale = 1e-3;
+                while scale < brightest {
+                    let mapped = operator.apply(RGB::new(hue) * scale);
+                    for (now, before) in mapped.0.into_iter().zip(previous.0) {
+                        assert!(now >= before, "{operator:?} falls at {scale}");
+                    }
+                    previous = mapped;
+                    scale *= 1.03;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn extended_reinhard_maps_white_to_one() {
+        for white in [0.5, 1.0, 4.0, 100.0] {
+            let mapped = ToneMap::ExtendedReinhard { white }.apply(RGB::new([white; 3]));
+            let one = mapped.0.iter().all(|n| (n - 1.0).abs() < 1e-12);
+            assert!(one, "white {white} maps to {mapped:?}");
+        }
+        let color = RGB::new([2.0, 1.0, 0.5]);
+        let reinhard = ToneMap::Reinhard.apply(color);
+        for white in [0.0, -1.0, Double::NAN, Double::INFINITY] {
+            let mapped = ToneMap::ExtendedReinhard { white }.apply(color);
+            assert_eq!(mapped, reinhard, "white {white}");
+        }
+    }
+
+    #[test]
+    fn srgb_known_values() {
+        let srgb = TransferFunction::Srgb;
+        assert_eq!(srgb.encode(0.0), 0.0);
+        assert!((srgb.encode(0.0031308) - 0.04045).abs() < 1e-5);
+        assert!((srgb.encode(0.5) - 0.7354).abs() < 1e-4);
+        assert!((srgb.encode(1.0) - 1.0).abs() < 1e-12);
+        // both pieces meet at the threshold
+        let below = srgb.encode(0.0031308);
+        let above = srgb.encode(0.0031308 + 1e-12);
+        assert!((above - below).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gamma_must_be_positive() {
+        assert!((TransferFunction::Gamma(2.2).encode(0.5) - 0.5_f64.powf(1.0 / 2.2)).abs() < 1e-12);
+        assert_eq!(TransferFunction::Gamma(2.2).encode(-0.5), 0.0);
+        for gamma in [0.0, -2.2, Double::NAN] {
+            for linear in [0.0, 0.25, 1.0] {
+                assert_eq!(TransferFunction::Gamma(gamma).encode(linear), linear);
+            }
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::f64::consts::PI;
+
+use crate::{
+    Double, color::RGB, hittable::plane::tangent_frame, light::directional::DirectionalLight,
+    sampler::Sampler, vec3::Vector3,
+};
+
+use super::{
+    Environment, distribution::Distribution2D, equirectangular_direction, equirectangular_uv,
+};
+
+/// Radiance of a luminance of 1 kcd/m², which puts a clear midday sky around 1
+const KILOCANDELA: Double = 0.1;
+/// Luminance of the sun outside the atmosphere, in kcd/m²
+const SUN_LUMINANCE: Double = 1.88e6;
+/// Angular radius of the sun seen from the earth, in radians
+const SUN_RADIUS: Double = 0.004_654;
+/// Resolution of the table the sky is importance sampled from
+const TABLE_SIZE: (usize, usize) = (128, 64);
+
+/// Clear daylight sky of Preetham, Shirley and Smits, "A Practical Analytic Model
+/// for Daylight" (1999), with the disk of the sun and a ground lit by both below the horizon.
+///
+/// The model covers the sun above the horizon, a lower sun is taken as setting.
+pub struct DaylightSky {
+    /// Unit vector from the scene towards the sun
+    sun_direction: Vector3,
+    /// Haziness of the atmosphere, from 2 for a very clear sky to 10 for a hazy one
+    turbidity: Double,
+    /// Reflectance of the ground below the horizon
+    ground_albedo: RGB,
+    /// Whether the disk of the sun is part of the sky
+    sun_disk: bool,
+    /// Scale of the radiance of the sky and the sun
+    intensity: Double,
+    perez: Perez,
+    /// Radiance of the disk of the sun, after crossing the atmosphere
+    sun_radiance: RGB,
+    ground_radiance: RGB,
+    distribution: Distribution2D,
+    /// Probability of sampling the disk of the sun rather than the rest of the sky
+    sun_probability: Double,
+}
+
+impl DaylightSky {
+    pub fn new(sun_direction: Vector3) -> Self {
+        Self::build(
+            sun_direction.unit_vector(),
+            3.0,
+            RGB::new([0.3; 3]),
+            true,
+            1.0,
+        )
+    }
+    /// The turbidity is kept within [1.7, 10], where the model was fitted.
+    pub fn with_turbidity(self, turbidity: Double) -> Self {
+        Self::build(
+            self.sun_direction,
+            turbidity.clamp(1.7, 10.0),
+            self.ground_albedo,
+            self.sun_disk,
+            self.intensity,
+        )
+    }
+    pub fn with_ground_albedo(self, ground_albedo: RGB) -> Self {
+        Self::build(
+            self.sun_direction,
+            self.turbidity,
+            ground_albedo,
+            self.sun_disk,
+            self.intensity,
+        )
+    }
+    /// Leave the sun out of the sky, to light the scene with [`DaylightSky::sun_light`] instead.
+    pub fn with_sun_disk(self, sun_disk: bool) -> Self {
+        Self::build(
+            self.sun_direction,
+            self.turbidity,
+            self.ground_albedo,
+            sun_disk,
+            self.intensity,
+        )
+    }
+    pub fn with_intensity(mut self, intensity: Double) -> Self {
+        self.intensity = intensity;
+        self
+    }
+    /// The sun as a [`DirectionalLight`] giving the irradiance of its disk.
+    ///
+    /// It is sharper and cheaper than the disk but invisible to rays, pair it with
+    /// `with_sun_disk(false)` so the sun is not counted twice.
+    pub fn sun_light(&self) -> DirectionalLight {
+        DirectionalLight::new(
+            -self.sun_direction,
+            self.sun_radiance * (sun_solid_angle() * self.intensity),
+        )
+    }
+    fn build(
+        sun_direction: Vector3,
+        turbidity: Double,
+        ground_albedo: RGB,
+        sun_disk: bool,
+        intensity: Double,
+    ) -> Self {
+        let perez = Perez::new(sun_direction, turbidity);
+        let sun_radiance = sun_radiance(sun_direction, turbidity);
+        // the ground is lit whether or not the disk is drawn
+        let sun_irradiance = sun_radiance * (sun_solid_angle() * sun_direction.y().max(0.0));
+        let ground_radiance = ground_albedo * (perez.irradiance() + sun_irradiance) / PI;
+
+        let (width, height) = TABLE_SIZE;
+        let mut weights = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let v = (y as Double + 0.5) / height as Double;
+            // rows near the poles cover a smaller solid angle
+            let sin_theta = (PI * v).sin();
+            for x in 0..width {
+                let u = (x as Double + 0.5) / width as Double;
+                let direction = equirectangular_direction([u, v]);
+                let radiance = if direction.y() > 0.0 {
+                    perez.radiance(direction)
+                } else {
+                    ground_radiance
+                };
+                weights.push(radiance.luminance().max(0.0) * sin_theta);
+            }
+        }
+        // each cell spans 2π / width by π / height radians
+        let sky_power = weights.iter().sum::<Double>() * 2.0 * PI * PI / (width * height) as Double;
+        let sun_power = if sun_disk {
+            sun_radiance.luminance() * sun_solid_angle()
+        } else {
+            0.0
+        };
+        let sun_probability = if sun_power > 0.0 {
+            sun_power / (sun_power + sky_power)
+        } else {
+            0.0
+        };
+        Self {
+            sun_direction,
+            turbidity,
+            ground_albedo,
+            sun_disk,
+            intensity,
+            perez,
+            sun_radiance,
+            ground_radiance,
+            distribution: Distribution2D::new(&weights, width, height),
+            sun_probability,
+        }
+    }
+    fn in_sun_disk(&self, direction: Vector3) -> bool {
+        self.sun_disk
+            && self.sun_direction.y() > 0.0
+            && direction.dot(self.sun_direction) >= SUN_RADIUS.cos()
+    }
+    /// Density by solid angle of sampling `direction` from the table of the sky.
+    fn sky_pdf(&self, direction: Vector3) -> Double {
+        let [u, v] = equirectangular_uv(direction);
+        let sin_theta = (PI * v).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let (width, height) = TABLE_SIZE;
+        let x = ((u * width as Double) as usize).min(width - 1);
+        let y = ((v * height as Double) as usize).min(height - 1);
+        // the table spans 2π by π radians, dω = 2π² sin θ du dv
+        self.distribution.pdf(x, y) / (2.0 * PI * PI * sin_theta)
+    }
+}
+
+impl Environment for DaylightSky {
+    fn radiance(&self, direction: Vector3) -> RGB {
+        if direction.y() <= 0.0 {
+            return self.ground_radiance * self.intensity;
+        }
+        let mut radiance = self.perez.radiance(direction);
+        if self.in_sun_disk(direction) {
+            radiance += self.sun_radiance;
+        }
+        radiance * self.intensity
+    }
+    fn pdf(&self, direction: Vector3) -> Double {
+        let sun_pdf = if self.in_sun_disk(direction) {
+            1.0 / sun_solid_angle()
+        } else {
+            0.0
+        };
+        self.sun_probability * sun_pdf + (1.0 - self.sun_probability) * self.sky_pdf(direction)
+    }
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<Vector3> {
+        if sampler.get_1d() >= self.sun_probability {
+            let uv = self.distribution.sample(sampler.get_2d())?;
+            return Some(equirectangular_direction(uv));
+        }
+        // uniform over the cone of the disk
+        let [u, v] = sampler.get_2d();
+        let cos_theta = 1.0 - u * (1.0 - SUN_RADIUS.cos());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        let (tangent, bitangent) = tangent_frame(self.sun_direction);
+        Some(
+            tangent * (sin_theta * phi.cos())
+                + bitangent * (sin_theta * phi.sin())
+                + self.sun_direction * cos_theta,
+        )
+    }
+}
+
+fn sun_solid_angle() -> Double {
+    2.0 * PI * (1.0 - SUN_RADIUS.cos())
+}
+
+/// Radiance of the sun seen through the atmosphere, reddened by Rayleigh and aerosol scattering
+/// (appendix of the paper, at one wavelength per channel), black once it has set.
+fn sun_radiance(sun_direction: Vector3, turbidity: Double) -> RGB {
+    let cos_theta = sun_direction.y();
+    if cos_theta <= 0.0 {
+        return RGB::default();
+    }
+    let theta_degrees = cos_theta.acos().to_degrees();
+    // relative optical mass, the length of the path through the atmosphere
+    let mass = 1.0 / (cos_theta + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+    // Ångström's turbidity coefficient, with α = 1.3
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmittance = |wavelength: Double| {
+        let rayleigh = (-0.008735 * wavelength.powf(-4.08) * mass).exp();
+        let aerosol = (-beta * wavelength.powf(-1.3) * mass).exp();
+        rayleigh * aerosol
+    };
+    // red, green and blue wavelengths, in micrometers
+    let transmitted = RGB::new([0.65, 0.55, 0.45].map(transmittance));
+    transmitted * (SUN_LUMINANCE * KILOCANDELA)
+}
+
+/// The sky luminance and chromaticity distributions of the model, without the sun.
+struct Perez {
+    sun_direction: Vector3,
+    /// Coefficients A to E of the Perez function, for Y, x and y
+    coefficients: [[Double; 5]; 3],
+    /// Y, x and y at the zenith, divided by the Perez function there
+    zenith: [Double; 3],
+}
+
+impl Perez {
+    fn new(sun_direction: Vector3, turbidity: Double) -> Self {
+        let t = turbidity;
+        // the fit only holds up to the horizon
+        let sun_direction = sun_direction
+            .with_y(sun_direction.y().max(0.01))
+            .unit_vector();
+        let theta_sun = sun_direction.y().acos();
+        let coefficients = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_sun);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let cubic =
+            |[a, b, c, d]: [Double; 4]| ((a * theta_sun + b) * theta_sun + c) * theta_sun + d;
+        let x = t * t * cubic([0.00166, -0.00375, 0.00209, 0.0])
+            + t * cubic([-0.02903, 0.06377, -0.03202, 0.00394])
+            + cubic([0.11693, -0.21196, 0.06052, 0.25886]);
+        let y = t * t * cubic([0.00275, -0.00610, 0.00317, 0.0])
+            + t * cubic([-0.04214, 0.08970, -0.04153, 0.00516])
+            + cubic([0.15346, -0.26756, 0.06670, 0.26688]);
+        let zenith = [luminance, x, y];
+        let mut perez = Self {
+            sun_direction,
+            coefficients,
+            zenith,
+        };
+        for (i, value) in perez.zenith.iter_mut().enumerate() {
+            *value /= perez_function(coefficients[i], 1.0, theta_sun);
+        }
+        perez
+    }
+    /// Radiance of the sky along the unit vector `direction`, above the horizon.
+    fn radiance(&self, direction: Vector3) -> RGB {
+        let cos_theta = direction.y().max(1e-3);
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] = [0, 1, 2]
+            .map(|i| self.zenith[i] * perez_function(self.coefficients[i], cos_theta, gamma));
+        xyy_to_rgb(x, y, luminance * KILOCANDELA)
+    }
+    /// Irradiance of the sky on an upward facing surface.
+    fn irradiance(&self) -> RGB {
+        // midpoint rule over the upper hemisphere, in cos θ and φ
+        let (rings, segments) = (32, 64);
+        let mut irradiance = RGB::default();
+        for i in 0..rings {
+            let cos_theta = (i as Double + 0.5) / rings as Double;
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            for j in 0..segments {
+                let phi = 2.0 * PI * (j as Double + 0.5) / segments as Double;
+                let direction =
+                    Vector3::new([sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin()]);
+                irradiance += self.radiance(direction) * cos_theta;
+            }
+        }
+        irradiance * (2.0 * PI / (rings * segments) as Double)
+    }
+}
+
+/// Relative distribution of the sky over the view zenith angle θ and the angle γ from the sun.
+fn perez_function([a, b, c, d, e]: [Double; 5], cos_theta: Double, gamma: Double) -> Double {
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos().powi(2))
+}
+
+/// Linear sRGB of the CIE chromaticity `x`, `y` and luminance `luminance`.
+fn xyy_to_rgb(x: Double, y: Double, luminance: Double) -> RGB {
+    if y <= 0.0 {
+        return RGB::default();
+    }
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    let rgb = [
+        3.240_454_2 * big_x - 1.537_138_5 * luminance - 0.498_531_4 * big_z,
+        -0.969_266_0 * big_x + 1.876_010_8 * luminance + 0.041_556_0 * big_z,
+        0.055_643_4 * big_x - 0.204_025_9 * luminance + 1.057_225_2 * big_z,
+    ];
+    RGB::new(rgb.map(|channel| channel.max(0.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{light::Light, sampler::independent::IndependentSampler, vec3::Point3};
+
+    fn sky() -> DaylightSky {
+        DaylightSky::new(Vector3::new([0.3, 0.6, -0.5]))
+    }
+
+    #[test]
+    fn pdf_matches_the_samples() {
+        let sky = sky();
+        let mut sampler = IndependentSampler::new(11);
+        let count = 200_000;
+        // E[f / pdf] over the samples is the integral of f over the sphere
+        let (mut solid_angle, mut height, mut in_sun) = (0.0, 0.0, 0);
+        for _ in 0..count {
+            let direction = sky.sample(&mut sampler).unwrap();
+            let pdf = sky.pdf(direction);
+            assert!(pdf > 0.0, "sampled {direction:?} has no density");
+            solid_angle += 1.0 / pdf;
+            height += direction.y() / pdf;
+            in_sun += sky.in_sun_disk(direction) as usize;
+        }
+        let solid_angle = solid_angle / count as Double;
+        assert!(
+            (solid_angle / (4.0 * PI) - 1.0).abs() < 0.02,
+            "{solid_angle}"
+        );
+        let height = height / count as Double;
+        assert!(height.abs() < 0.05 * 4.0 * PI, "{height}");
+        let in_sun = in_sun as Double / count as Double;
+        assert!(sky.sun_probability > 0.1);
+        assert!((in_sun - sky.sun_probability).abs() < 0.01, "{in_sun}");
+        // and the density integrates to 1, away from the tiny disk of the sun
+        let sky = sky.with_sun_disk(false);
+        let mut integral = 0.0;
+        for _ in 0..count {
+            integral += sky.pdf(Vector3::random_unit(&mut sampler));
+        }
+        let integral = integral * 4.0 * PI / count as Double;
+        assert!((integral - 1.0).abs() < 0.02, "{integral}");
+    }
+
+    #[test]
+    fn no_sun_once_it_has_set() {
+        let sunset = DaylightSky::new(Vector3::new([0.8, -0.1, 0.0]));
+        assert_eq!(sunset.sun_probability, 0.0);
+        assert_eq!(sunset.sun_light().irradiance.0, [0.0; 3]);
+        assert!(!sunset.in_sun_disk(sunset.sun_direction));
+        // the sky still glows, and is still sampled
+        let mut sampler = IndependentSampler::new(3);
+        let direction = sunset.sample(&mut sampler).unwrap();
+        assert!(sunset.pdf(direction) > 0.0);
+        assert!(sunset.radiance(Vector3::new([0.0, 1.0, 0.0])).luminance() > 0.0);
+        let sunrise = DaylightSky::new(Vector3::new([0.8, 0.1, 0.0]));
+        assert!(sunrise.sun_probability > 0.0);
+    }
+
+    #[test]
+    fn sun_light_matches_the_disk() {
+        let with_disk = sky().with_intensity(0.5);
+        let without_disk = sky().with_sun_disk(false).with_intensity(0.5);
+        // irradiance of the disk alone, facing the sun: midpoint rule over a cone around it
+        let sun = with_disk.sun_direction;
+        let (tangent, bitangent) = tangent_frame(sun);
+        let (rings, segments) = (64, 64);
+        let cone = 2.0 * SUN_RADIUS;
+        let mut disk = RGB::default();
+        for i in 0..rings {
+            let theta = cone * (i as Double + 0.5) / rings as Double;
+            for j in 0..segments {
+                let phi = 2.0 * PI * (j as Double + 0.5) / segments as Double;
+                let direction = tangent * (theta.sin() * phi.cos())
+                    + bitangent * (theta.sin() * phi.sin())
+                    + sun * theta.cos();
+                let sun_only = with_disk.radiance(direction) - without_disk.radiance(direction);
+                // dω = sin θ dθ dφ
+                disk += sun_only * (theta.cos() * theta.sin());
+            }
+        }
+        let disk = disk * (cone * 2.0 * PI / (rings * segments) as Double);
+        let light = without_disk.sun_light();
+        let sample = light.sample_li(Point3::default()).unwrap();
+        assert!((sample.direction.dot(sun) - 1.0).abs() < 1e-12);
+        for (light, disk) in sample.irradiance.0.into_iter().zip(disk.0) {
+            assert!(light > 0.0);
+            assert!((light / disk - 1.0).abs() < 1e-3, "{light} against {disk}");
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::Double;
+
+/// Piecewise constant density over [0,1]^2, one cell per pixel:
+/// a row is picked from the marginal distribution, then a column within it.
+pub(super) struct Distribution2D {
+    width: usize,
+    height: usize,
+    /// Cumulative weights of each row, normalized, `width + 1` values per row
+    conditional: Vec<Double>,
+    /// Cumulative weights of the rows, normalized, `height + 1` values
+    marginal: Vec<Double>,
+    /// Density of each cell, relative to the unit square
+    densities: Vec<Double>,
+}
+
+impl Distribution2D {
+    pub(super) fn new(weights: &[Double], width: usize, height: usize) -> Self {
+        let total: Double = weights.iter().sum();
+        let mut conditional = Vec::with_capacity((width + 1) * height);
+        let mut row_sums = Vec::with_capacity(height);
+        for row in weights.chunks_exact(width.max(1)) {
+            row_sums.push(row.iter().sum::<Double>());
+            conditional.extend(cumulative(row));
+        }
+        let marginal = cumulative(&row_sums);
+        let cells = (width * height) as Double;
+        let densities = if total > 0.0 {
+            weights.iter().map(|w| w / total * cells).collect()
+        } else {
+            vec![0.0; weights.len()]
+        };
+        Self {
+            width,
+            height,
+            conditional,
+            marginal,
+            densities,
+        }
+    }
+    pub(super) fn pdf(&self, x: usize, y: usize) -> Double {
+        self.densities
+            .get(y * self.width + x)
+            .copied()
+            .unwrap_or(0.0)
+    }
+    /// Point of the unit square, `None` if every weight is zero.
+    pub(super) fn sample(&self, [u, v]: [Double; 2]) -> Option<[Double; 2]> {
+        if self.densities.iter().all(|&density| density <= 0.0) {
+            return None;
+        }
+        let (y, dy) = sample_cumulative(&self.marginal, v);
+        let row = &self.conditional[y * (self.width + 1)..(y + 1) * (self.width + 1)];
+        let (x, dx) = sample_cumulative(row, u);
+        Some([
+            (x as Double + dx) / self.width as Double,
+            (y as Double + dy) / self.height as Double,
+        ])
+    }
+}
+
+/// Normalized running sum, starting at 0 and ending at 1.
+fn cumulative(weights: &[Double]) -> Vec<Double> {
+    let mut cdf = Vec::with_capacity(weights.len() + 1);
+    let mut sum = 0.0;
+    cdf.push(0.0);
+    for weight in weights {
+        sum += weight;
+        cdf.push(sum);
+    }
+    if sum > 0.0 {
+        cdf.iter_mut().for_each(|value| *value /= sum);
+    } else {
+        // nothing to prefer, fall back to uniform
+        let count = weights.len().max(1) as Double;
+        cdf.iter_mut()
+            .enumerate()
+            .for_each(|(i, value)| *value = i as Double / count);
+    }
+    cdf
+}
+
+/// Index of the segment of `cdf` containing `u`, and the offset of `u` within it.
+fn sample_cumulative(cdf: &[Double], u: Double) -> (usize, Double) {
+    // first entry above u, the segment starts one before
+    let index = cdf
+        .partition_point(|&value| value <= u)
+        .clamp(1, cdf.len() - 1)
+        - 1;
+    let width = cdf[index + 1] - cdf[index];
+    let offset = if width > 0.0 {
+        ((u - cdf[index]) / width).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (index, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    /// 4 x 3 cells, some of them empty, including whole rows and the corners.
+    const WEIGHTS: [Double; 12] = [
+        0.0, 1.0, 3.0, 0.0, //
+        0.0, 0.0, 0.0, 0.0, //
+        2.0, 0.5, 0.0, 5.5,
+    ];
+
+    fn cell(distribution: &Distribution2D, [u, v]: [Double; 2]) -> usize {
+        let x = (u * distribution.width as Double) as usize;
+        let y = (v * distribution.height as Double) as usize;
+        y * distribution.width + x
+    }
+
+    #[test]
+    fn samples_follow_the_density() {
+        let distribution = Distribution2D::new(&WEIGHTS, 4, 3);
+        // the density averages to 1 over the unit square
+        let total: Double = (0..3)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .map(|(x, y)| distribution.pdf(x, y))
+            .sum();
+        assert!((total / 12.0 - 1.0).abs() < 1e-12);
+        let mut rng = Rng::new(9);
+        let count = 100_000;
+        let mut histogram = [0; 12];
+        for _ in 0..count {
+            let uv = distribution
+                .sample([rng.random_double(), rng.random_double()])
+                .unwrap();
+            assert!(uv.iter().all(|n| (0.0..=1.0).contains(n)));
+            histogram[cell(&distribution, uv)] += 1;
+        }
+        for (i, hits) in histogram.into_iter().enumerate() {
+            let expected = distribution.pdf(i % 4, i / 4) / 12.0;
+            let fraction = hits as Double / count as Double;
+            assert!(
+                (fraction - expected).abs() < 0.01,
+                "cell {i}: {fraction} for {expected}"
+            );
+            if WEIGHTS[i] == 0.0 {
+                assert_eq!(hits, 0, "empty cell {i} was sampled");
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_samples_skip_empty_cells() {
+        let distribution = Distribution2D::new(&WEIGHTS, 4, 3);
+        let almost_one = 1.0 - Double::EPSILON;
+        for u in [0.0, almost_one] {
+            for v in [0.0, almost_one] {
+                let uv = distribution.sample([u, v]).unwrap();
+                assert!(WEIGHTS[cell(&distribution, uv)] > 0.0, "{uv:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn no_weight_cannot_be_sampled() {
+        let distribution = Distribution2D::new(&[0.0; 6], 3, 2);
+        assert!(distribution.sample([0.5, 0.5]).is_none());
+        assert_eq!(distribution.pdf(1, 1), 0.0);
+        assert_eq!(distribution.pdf(3, 0), 0.0);
+    }
+}
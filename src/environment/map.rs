@@ -0,0 +1,175 @@
+use std::f64::consts::PI;
+use std::io;
+use std::path::Path;
+
+use crate::{Double, color::RGB, image::Image, sampler::Sampler, vec3::Vector3};
+
+use super::{
+    Environment, distribution::Distribution2D, equirectangular_direction, equirectangular_uv,
+};
+
+/// Environment read from an equirectangular (latitude-longitude) image.
+///
+/// The top row looks up (+Y), the center of the image looks down -Z, and `u`
+/// grows towards +X. Directions are sampled in proportion to the luminance of the pixels.
+pub struct EnvironmentMap {
+    image: Image,
+    /// Rotation around the Y axis, in degrees
+    rotation: Double,
+    /// Scale of the radiance of the image
+    intensity: Double,
+    distribution: Distribution2D,
+}
+
+impl EnvironmentMap {
+    pub fn new(image: Image) -> Self {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        // rows near the poles cover a smaller solid angle
+        let weights: Vec<Double> = image
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                let sin_theta = (PI * (y as Double + 0.5) / height as Double).sin();
+                row.iter()
+                    .map(move |pixel| pixel.luminance().max(0.0) * sin_theta)
+            })
+            .collect();
+        Self {
+            distribution: Distribution2D::new(&weights, width, height),
+            image,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+    /// Read the `.hdr` or `.pfm` image at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(Image::load(path)?))
+    }
+    pub fn with_rotation(mut self, degrees: Double) -> Self {
+        self.rotation = degrees;
+        self
+    }
+    pub fn with_intensity(mut self, intensity: Double) -> Self {
+        self.intensity = intensity;
+        self
+    }
+    /// Image coordinates in [0,1]^2 of the unit vector `direction`.
+    fn uv(&self, direction: Vector3) -> [Double; 2] {
+        equirectangular_uv(rotate_y(direction, -self.rotation))
+    }
+    fn direction(&self, uv: [Double; 2]) -> Vector3 {
+        rotate_y(equirectangular_direction(uv), self.rotation)
+    }
+    fn pixel(&self, [u, v]: [Double; 2]) -> (u32, u32) {
+        let (width, height) = (self.image.width(), self.image.height());
+        let x = ((u * width as Double) as u32).min(width.saturating_sub(1));
+        let y = ((v * height as Double) as u32).min(height.saturating_sub(1));
+        (x, y)
+    }
+}
+
+impl Environment for EnvironmentMap {
+    fn radiance(&self, direction: Vector3) -> RGB {
+        if self.image.pixels().is_empty() {
+            return RGB::default();
+        }
+        let (x, y) = self.pixel(self.uv(direction));
+        self.image.get(x, y) * self.intensity
+    }
+    fn pdf(&self, direction: Vector3) -> Double {
+        let uv = self.uv(direction);
+        let sin_theta = (PI * uv[1]).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = self.pixel(uv);
+        // the image spans 2π by π radians, dω = 2π² sin θ du dv
+        self.distribution.pdf(x as usize, y as usize) / (2.0 * PI * PI * sin_theta)
+    }
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<Vector3> {
+        let uv = self.distribution.sample(sampler.get_2d())?;
+        Some(self.direction(uv))
+    }
+}
+
+fn rotate_y(v: Vector3, degrees: Double) -> Vector3 {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let [x, y, z] = v.0;
+    Vector3::new([cos * x + sin * z, y, -sin * x + cos * z])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::independent::IndependentSampler;
+
+    /// 8 x 4 map with a bright spot, a dim band and black pixels.
+    fn map() -> EnvironmentMap {
+        let mut image = Image::new(8, 4);
+        for x in 0..8 {
+            image.set(x, 2, RGB::new([0.2, 0.1, 0.4]));
+        }
+        image.set(5, 1, RGB::new([6.0, 5.0, 4.0]));
+        image.set(0, 0, RGB::new([1.0; 3]));
+        EnvironmentMap::new(image)
+    }
+
+    #[test]
+    fn samples_follow_the_pdf() {
+        for rotation in [0.0, 70.0] {
+            let map = map().with_rotation(rotation);
+            let mut sampler = IndependentSampler::new(4);
+            let count = 100_000;
+            let mut solid_angle = 0.0;
+            let mut histogram = [0; 32];
+            for _ in 0..count {
+                let direction = map.sample(&mut sampler).unwrap();
+                let pdf = map.pdf(direction);
+                assert!(pdf > 0.0, "sampled {direction:?} has no density");
+                assert!(map.radiance(direction).luminance() > 0.0);
+                solid_angle += 1.0 / pdf;
+                let (x, y) = map.pixel(map.uv(direction));
+                histogram[(y * 8 + x) as usize] += 1;
+            }
+            // E[1 / pdf] is the solid angle of the pixels which are not black
+            let lit = [(0, 0), (5, 1)]
+                .into_iter()
+                .chain((0..8).map(|x| (x, 2)))
+                .map(|(_, y)| pixel_solid_angle(y))
+                .sum::<Double>();
+            let solid_angle = solid_angle / count as Double;
+            assert!(
+                (solid_angle / lit - 1.0).abs() < 0.02,
+                "{solid_angle} for {lit}"
+            );
+            // each pixel is picked as often as its density over the pixel says
+            for (i, hits) in histogram.into_iter().enumerate() {
+                let (x, y) = (i % 8, i / 8);
+                let direction =
+                    map.direction([(x as Double + 0.5) / 8.0, (y as Double + 0.5) / 4.0]);
+                let sin_theta = (PI * (y as Double + 0.5) / 4.0).sin();
+                let expected = map.pdf(direction) * sin_theta * 2.0 * PI * PI / 32.0;
+                let fraction = hits as Double / count as Double;
+                assert!(
+                    (fraction - expected).abs() < 0.01,
+                    "pixel {i}: {fraction} for {expected}"
+                );
+            }
+        }
+    }
+
+    /// Solid angle covered by one pixel of row `y` of the 8 x 4 map.
+    fn pixel_solid_angle(y: u32) -> Double {
+        let [top, bottom] = [y, y + 1].map(|row| (PI * row as Double / 4.0).cos());
+        2.0 * PI / 8.0 * (top - bottom)
+    }
+
+    #[test]
+    fn black_map_cannot_be_sampled() {
+        let map = EnvironmentMap::new(Image::new(8, 4));
+        let mut sampler = IndependentSampler::new(1);
+        assert!(map.sample(&mut sampler).is_none());
+        assert_eq!(map.pdf(Vector3::new([0.0, 0.6, 0.8])), 0.0);
+        assert_eq!(map.radiance(Vector3::new([0.0, 0.6, 0.8])).0, [0.0; 3]);
+    }
+}
@@ -0,0 +1,67 @@
+use std::f64::consts::PI;
+
+use crate::{Double, color::RGB, sampler::Sampler, vec3::Vector3};
+pub mod daylight;
+mod distribution;
+pub mod map;
+
+/// Radiance arriving from beyond the scene, seen by the rays which escape it.
+pub trait Environment: Send + Sync {
+    /// Radiance arriving from the unit vector `direction`.
+    fn radiance(&self, direction: Vector3) -> RGB;
+    /// Density, by solid angle, of [`Environment::sample`] choosing `direction`.
+    ///
+    /// Zero for environments which cannot be sampled.
+    fn pdf(&self, _direction: Vector3) -> Double {
+        0.0
+    }
+    /// Unit vector towards the environment, chosen roughly in proportion to its radiance,
+    /// `None` if the environment cannot be sampled.
+    fn sample(&self, _sampler: &mut dyn Sampler) -> Option<Vector3> {
+        None
+    }
+}
+
+/// The same color in every direction, black for scenes lit only by emitters.
+impl Environment for RGB {
+    fn radiance(&self, _direction: Vector3) -> RGB {
+        *self
+    }
+}
+
+/// White to light blue gradient along the Y axis.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sky;
+
+impl Environment for Sky {
+    fn radiance(&self, direction: Vector3) -> RGB {
+        // y ∈ [-1, 1], + 1 → ∈ [0, 2],* 0.5 → ∈ [0, 1]
+        //interpolation factor
+        let factor = (direction.y() + 1.0) * 0.5;
+        // background color
+        let white = RGB::new([1.0; 3]);
+        let blue = RGB::new([0.5, 0.7, 1.0]);
+        // linear blend / lerp
+        white * (1.0 - factor) + blue * factor
+    }
+}
+
+/// Coordinates in [0,1]^2 of the unit vector `direction` on a latitude-longitude image:
+/// `v` runs from +Y down to -Y, `u` from +Z round through -X, -Z and +X.
+fn equirectangular_uv(direction: Vector3) -> [Double; 2] {
+    let [x, y, z] = direction.0;
+    let phi = x.atan2(-z);
+    let theta = y.clamp(-1.0, 1.0).acos();
+    [0.5 + phi / (2.0 * PI), theta / PI]
+}
+
+/// Inverse of [`equirectangular_uv`].
+fn equirectangular_direction([u, v]: [Double; 2]) -> Vector3 {
+    let phi = 2.0 * PI * (u - 0.5);
+    let theta = PI * v;
+    Vector3::new([
+        theta.sin() * phi.sin(),
+        theta.cos(),
+        -theta.sin() * phi.cos(),
+    ])
+}
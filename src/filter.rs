@@ -0,0 +1,204 @@
+use crate::{Double, color::RGB};
+
+/// Pixel reconstruction filter.
+///
+/// Samples are jittered over `[-radius, radius]^2` around the pixel center
+/// and averaged with the weight returned by [`Filter::evaluate`].
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Filter {
+    /// Every sample inside the pixel footprint has the same weight.
+    #[default]
+    Box,
+    /// Weight falls off linearly to zero at one pixel.
+    Tent,
+    /// `exp(-alpha * x^2)`, shifted so it reaches zero at the radius.
+    Gaussian { radius: Double, alpha: Double },
+    /// Mitchell-Netravali cubic, `b = c = 1/3` is the recommended default.
+    Mitchell { b: Double, c: Double },
+}
+
+impl Filter {
+    pub fn gaussian() -> Self {
+        Self::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        }
+    }
+    pub fn mitchell() -> Self {
+        Self::Mitchell {
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+    /// Half width of the filter support, in pixels.
+    pub fn radius(&self) -> Double {
+        match *self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian { radius, .. } => radius,
+            Filter::Mitchell { .. } => 2.0,
+        }
+    }
+    /// The filter is separable: `f(x, y) = f(x) * f(y)`.
+    pub fn evaluate(&self, x: Double, y: Double) -> Double {
+        self.evaluate_1d(x) * self.evaluate_1d(y)
+    }
+    fn evaluate_1d(&self, x: Double) -> Double {
+        let x = x.abs();
+        match *self {
+            Filter::Box => {
+                if x <= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Filter::Tent => (1.0 - x).max(0.0),
+            Filter::Gaussian { radius, alpha } => {
+                let edge = (-alpha * radius * radius).exp();
+                ((-alpha * x * x).exp() - edge).max(0.0)
+            }
+            Filter::Mitchell { b, c } => {
+                // piecewise cubic over [0,1) and [1,2)
+                let x2 = x * x;
+                let x3 = x2 * x;
+                let value = if x < 1.0 {
+                    (12.0 - 9.0 * b - 6.0 * c) * x3
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x2
+                        + (6.0 - 2.0 * b)
+                } else if x < 2.0 {
+                    (-b - 6.0 * c) * x3
+                        + (6.0 * b + 30.0 * c) * x2
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                } else {
+                    0.0
+                };
+                value / 6.0
+            }
+        }
+    }
+}
+
+/// The samples of one pixel, weighted by a [`Filter`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct PixelSum {
+    weighted: RGB,
+    weight_sum: Double,
+    abs_weight_sum: Double,
+    unweighted: RGB,
+    count: u32,
+}
+
+impl PixelSum {
+    pub(crate) fn add(&mut self, color: RGB, weight: Double) {
+        self.weighted += color * weight;
+        self.weight_sum += weight;
+        self.abs_weight_sum += weight.abs();
+        self.unweighted += color;
+        self.count += 1;
+    }
+    /// The filtered color of the pixel, black without samples.
+    ///
+    /// Negative channels left by negative weights are clamped to zero, but NaN and
+    /// infinite values are kept so that they show up in the image.
+    pub(crate) fn resolve(&self) -> RGB {
+        if self.count == 0 {
+            return RGB::default();
+        }
+        // The negative lobes of a filter like Mitchell can all but cancel the
+        // positive weights at low sample counts, dividing by what is left would
+        // blow the pixel up or flip its sign: fall back to the plain average.
+        let color = if self.weight_sum >= 0.25 * self.abs_weight_sum {
+            self.weighted / self.weight_sum
+        } else {
+            self.unweighted / self.count as Double
+        };
+        RGB::new(color.0.map(|channel| {
+            if channel.is_finite() && channel < 0.0 {
+                0.0
+            } else {
+                channel
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    const FILTERS: [Filter; 4] = [
+        Filter::Box,
+        Filter::Tent,
+        Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        },
+        Filter::Mitchell {
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        },
+    ];
+
+    /// `count` samples of `color` jittered over the filter support.
+    fn jittered(filter: Filter, color: RGB, count: u32, rng: &mut Rng) -> PixelSum {
+        let radius = filter.radius();
+        let mut sum = PixelSum::default();
+        for _ in 0..count {
+            let dx = rng.random_range(-radius, radius);
+            let dy = rng.random_range(-radius, radius);
+            let weight = filter.evaluate(dx, dy);
+            if weight != 0.0 {
+                sum.add(color, weight);
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn constant_radiance_is_kept_by_every_filter() {
+        let color = RGB::new([0.5, 0.25, 2.0]);
+        let mut rng = Rng::new(5);
+        for filter in FILTERS {
+            for count in [1, 2, 3, 16, 256] {
+                for _ in 0..100 {
+                    let resolved = jittered(filter, color, count, &mut rng).resolve();
+                    let error = (resolved - color).0.map(Double::abs);
+                    assert!(
+                        error.iter().all(|&e| e < 1e-9),
+                        "{filter:?} with {count} samples gave {resolved:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cancelling_weights_fall_back_to_the_average() {
+        let mut sum = PixelSum::default();
+        sum.add(RGB::new([1.0; 3]), 0.5);
+        sum.add(RGB::new([3.0; 3]), -0.45);
+        assert_eq!(sum.resolve().0, [2.0; 3]);
+
+        // enough positive weight, the negative lobe only sharpens
+        let mut sum = PixelSum::default();
+        sum.add(RGB::new([1.0; 3]), 1.0);
+        sum.add(RGB::new([3.0; 3]), -0.25);
+        assert_eq!(sum.resolve().0, [1.0 / 3.0; 3]);
+        sum.add(RGB::new([6.0; 3]), -0.25);
+        assert_eq!(sum.resolve().0, [0.0; 3]);
+    }
+
+    #[test]
+    fn only_finite_negatives_are_clamped() {
+        let mut sum = PixelSum::default();
+        assert_eq!(sum.resolve().0, [0.0; 3]);
+        sum.add(RGB::new([f64::NAN, -1.0, f64::INFINITY]), 1.0);
+        let [nan, negative, infinite] = sum.resolve().0;
+        assert!(nan.is_nan());
+        assert_eq!(negative, 0.0);
+        assert_eq!(infinite, f64::INFINITY);
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial},
+    ray::Ray,
+    vec3::{Point3, Vector3},
+};
+
+use super::{HitRecord, quad::Quad};
+
+/// Axis-aligned box between two opposite corners, made of six [`Quad`]s facing outward.
+pub struct AxisBox {
+    sides: [Quad; 6],
+}
+
+impl Hittable for AxisBox {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let mut record = None;
+        let mut range = ray_t_range;
+        for side in &self.sides {
+            if let Some(temp_rec) = side.hit(ray, range) {
+                range.max = temp_rec.ray_t;
+                record = Some(temp_rec)
+            }
+        }
+        record
+    }
+    fn bounding_box(&self) -> Aabb {
+        self.sides.iter().fold(Aabb::EMPTY, |acc, side| {
+            Aabb::enclosing(acc, side.bounding_box())
+        })
+    }
+}
+
+impl AxisBox {
+    /// The corners may be given in any order.
+    pub fn new(a: Point3, b: Point3) -> Self {
+        let min = Point3::new([0, 1, 2].map(|n| a[n].min(b[n])));
+        let max = Point3::new([0, 1, 2].map(|n| a[n].max(b[n])));
+        let dx = Vector3::default().with_x(max.x() - min.x());
+        let dy = Vector3::default().with_y(max.y() - min.y());
+        let dz = Vector3::default().with_z(max.z() - min.z());
+        let corner = |x: f64, y: f64, z: f64| Point3::new([x, y, z]);
+        // u x v of each side points out of the box
+        let sides = [
+            // front, back
+            Quad::new(corner(min.x(), min.y(), max.z()), dx, dy),
+            Quad::new(corner(max.x(), min.y(), min.z()), -dx, dy),
+            // right, left
+            Quad::new(corner(max.x(), min.y(), max.z()), -dz, dy),
+            Quad::new(corner(min.x(), min.y(), min.z()), dz, dy),
+            // top, bottom
+            Quad::new(corner(min.x(), max.y(), max.z()), dx, -dz),
+            Quad::new(corner(min.x(), min.y(), min.z()), dx, dz),
+        ];
+        Self { sides }
+    }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(self, material: SharedMaterial) -> Self {
+        Self {
+            sides: self
+                .sides
+                .map(|side| side.with_shared_material(material.clone())),
+        }
+    }
+    pub fn sides(&self) -> &[Quad; 6] {
+        &self.sides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: Interval = Interval {
+        min: 0.001,
+        max: f64::INFINITY,
+    };
+
+    #[test]
+    fn every_side_faces_outward() {
+        let axis_box = AxisBox::new(Point3::new([1.0, 2.0, 3.0]), Point3::new([-1.0, 0.0, 1.0]));
+        let center = Point3::new([0.0, 1.0, 2.0]);
+        for n in 0..3 {
+            for sign in [-1.0, 1.0] {
+                let mut outward = Vector3::default();
+                outward.0[n] = sign;
+                // from outside, hitting the side at a distance of 1
+                let ray = Ray::new(center + outward * 2.0, -outward);
+                let record = axis_box.hit(&ray, RANGE).unwrap();
+                assert!((record.ray_t - 1.0).abs() < 1e-12);
+                assert!(record.normal_direction.is_outward());
+                assert_eq!(record.normal, outward);
+                // from inside, the same side is hit from the back
+                let ray = Ray::new(center, outward);
+                let record = axis_box.hit(&ray, RANGE).unwrap();
+                assert!((record.ray_t - 1.0).abs() < 1e-12);
+                assert!(!record.normal_direction.is_outward());
+                assert_eq!(record.normal, -outward);
+            }
+        }
+    }
+
+    #[test]
+    fn closest_side_wins() {
+        let axis_box = AxisBox::new(Point3::default(), Point3::new([1.0, 1.0, 1.0]));
+        let ray = Ray::new(
+            Point3::new([0.5, 0.5, 3.0]),
+            Vector3::default().with_z(-1.0),
+        );
+        let record = axis_box.hit(&ray, RANGE).unwrap();
+        assert!((record.ray_t - 2.0).abs() < 1e-12);
+        assert!(axis_box.hit(&ray, Interval::new(0.001, 1.5)).is_none());
+        let miss = Ray::new(
+            Point3::new([1.5, 0.5, 3.0]),
+            Vector3::default().with_z(-1.0),
+        );
+        assert!(axis_box.hit(&miss, RANGE).is_none());
+    }
+
+    #[test]
+    fn corners_in_any_order() {
+        let [a, b] = [[2.0, -1.0, 0.5], [-2.0, 1.0, -0.5]];
+        let corners = [(a, b), (b, a), ([a[0], b[1], a[2]], [b[0], a[1], b[2]])];
+        for (a, b) in corners {
+            let bbox = AxisBox::new(Point3::new(a), Point3::new(b)).bounding_box();
+            // the flat sides are padded a little
+            for (n, extent) in [2.0, 1.0, 0.5].into_iter().enumerate() {
+                let interval = bbox.axis_interval(n);
+                assert!((interval.min + extent).abs() < 1e-3);
+                assert!((interval.max - extent).abs() < 1e-3);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{Double, aabb::Aabb, interval::Interval, ray::Ray};
+
+use super::{HitRecord, Hittable, HittableBox, HittableList};
+
+/// Number of buckets the centroid range is split into when searching for the best split plane
+const BIN_COUNT: usize = 12;
+/// Nodes with at most this many primitives become leaves when splitting does not pay off
+const MAX_LEAF_SIZE: usize = 4;
+/// Cost of visiting a node relative to intersecting one primitive
+const TRAVERSAL_COST: Double = 1.0;
+
+/// Bounding volume hierarchy, split by the surface area heuristic (SAH).
+pub struct BvhNode {
+    bbox: Aabb,
+    children: Children,
+}
+
+enum Children {
+    Leaf(Vec<HittableBox>),
+    Inner(Box<BvhNode>, Box<BvhNode>),
+}
+
+struct Primitive {
+    bbox: Aabb,
+    centroid: [Double; 3],
+    object: HittableBox,
+}
+
+impl From<HittableList> for BvhNode {
+    fn from(list: HittableList) -> Self {
+        Self::new(list.objects)
+    }
+}
+
+impl BvhNode {
+    pub fn new(objects: Vec<HittableBox>) -> Self {
+        let primitives = objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                Primitive {
+                    bbox,
+                    centroid: bbox.centroid().0,
+                    object,
+                }
+            })
+            .collect();
+        Self::build(primitives)
+    }
+    fn build(primitives: Vec<Primitive>) -> Self {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| Aabb::enclosing(acc, p.bbox));
+        let count = primitives.len();
+        if count <= 1 {
+            return Self::leaf(bbox, primitives);
+        }
+        // bounds of the centroids decide the split axis and the bins
+        let centroid_bounds = primitives.iter().fold(Aabb::EMPTY, |acc, p| {
+            let c = p.centroid;
+            Aabb::enclosing(
+                acc,
+                Aabb {
+                    x: Interval::new(c[0], c[0]),
+                    y: Interval::new(c[1], c[1]),
+                    z: Interval::new(c[2], c[2]),
+                },
+            )
+        });
+        let axis = centroid_bounds.longest_axis();
+        let axis_range = centroid_bounds.axis_interval(axis);
+        if axis_range.size() <= 0.0 {
+            // all centroids coincide, no plane can separate them
+            return if count <= MAX_LEAF_SIZE {
+                Self::leaf(bbox, primitives)
+            } else {
+                Self::split_at_median(bbox, primitives, axis)
+            };
+        }
+
+        let bin_of = |p: &Primitive| {
+            let offset = (p.centroid[axis] - axis_range.min) / axis_range.size();
+            ((offset * BIN_COUNT as Double) as usize).min(BIN_COUNT - 1)
+        };
+        let mut bins = [(0usize, Aabb::EMPTY); BIN_COUNT];
+        for p in &primitives {
+            let bin = &mut bins[bin_of(p)];
+            bin.0 += 1;
+            bin.1 = Aabb::enclosing(bin.1, p.bbox);
+        }
+        // sweep from both ends to get the primitive count and area on each side of every plane
+        let mut right_area = [0.0; BIN_COUNT];
+        let mut right_count = [0usize; BIN_COUNT];
+        let (mut acc_count, mut acc_box) = (0, Aabb::EMPTY);
+        for i in (1..BIN_COUNT).rev() {
+            acc_count += bins[i].0;
+            acc_box = Aabb::enclosing(acc_box, bins[i].1);
+            right_count[i] = acc_count;
+            right_area[i] = acc_box.surface_area();
+        }
+        let parent_area = bbox.surface_area().max(Double::MIN_POSITIVE);
+        let (mut best_plane, mut best_cost) = (0, Double::INFINITY);
+        let (mut acc_count, mut acc_box) = (0, Aabb::EMPTY);
+        for plane in 1..BIN_COUNT {
+            acc_count += bins[plane - 1].0;
+            acc_box = Aabb::enclosing(acc_box, bins[plane - 1].1);
+            if acc_count == 0 || right_count[plane] == 0 {
+                continue;
+            }
+            let cost = TRAVERSAL_COST
+                + (acc_box.surface_area() * acc_count as Double
+                    + right_area[plane] * right_count[plane] as Double)
+                    / parent_area;
+            if cost < best_cost {
+                (best_plane, best_cost) = (plane, cost);
+            }
+        }
+
+        let leaf_cost = count as Double;
+        if best_plane == 0 || (best_cost >= leaf_cost && count <= MAX_LEAF_SIZE) {
+            return if count <= MAX_LEAF_SIZE {
+                Self::leaf(bbox, primitives)
+            } else {
+                Self::split_at_median(bbox, primitives, axis)
+            };
+        }
+        let (left, right): (Vec<_>, Vec<_>) =
+            primitives.into_iter().partition(|p| bin_of(p) < best_plane);
+        Self::inner(bbox, left, right)
+    }
+    fn split_at_median(bbox: Aabb, mut primitives: Vec<Primitive>, axis: usize) -> Self {
+        primitives.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+        let right = primitives.split_off(primitives.len() / 2);
+        Self::inner(bbox, primitives, right)
+    }
+    fn inner(bbox: Aabb, left: Vec<Primitive>, right: Vec<Primitive>) -> Self {
+        Self {
+            bbox,
+            children: Children::Inner(Box::new(Self::build(left)), Box::new(Self::build(right))),
+        }
+    }
+    fn leaf(bbox: Aabb, primitives: Vec<Primitive>) -> Self {
+        Self {
+            bbox,
+            children: Children::Leaf(primitives.into_iter().map(|p| p.object).collect()),
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        if !self.bbox.hit(ray, ray_t_range) {
+            return None;
+        }
+        match &self.children {
+            Children::Leaf(objects) => {
+                let mut record = None;
+                let mut range = ray_t_range;
+                for obj in objects {
+                    if let Some(temp_rec) = obj.hit(ray, range) {
+                        range.max = temp_rec.ray_t;
+                        record = Some(temp_rec)
+                    }
+                }
+                record
+            }
+            Children::Inner(left, right) => {
+                let hit_left = left.hit(ray, ray_t_range);
+                let range = match &hit_left {
+                    Some(record) => Interval::new(ray_t_range.min, record.ray_t),
+                    None => ray_t_range,
+                };
+                right.hit(ray, range).or(hit_left)
+            }
+        }
+    }
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable::{HittableList, sphere::Sphere},
+        random::Rng,
+        vec3::Point3,
+    };
+
+    fn random_point(rng: &mut Rng, extent: Double) -> Point3 {
+        Point3::new([(); 3].map(|_| rng.random_range(-extent, extent)))
+    }
+
+    fn spheres(count: usize, seed: u64) -> Vec<HittableBox> {
+        let mut rng = Rng::new(seed);
+        (0..count)
+            .map(|_| {
+                let center = random_point(&mut rng, 10.0);
+                Sphere::from_point3(center)
+                    .with_radius(rng.random_range(0.1, 1.5))
+                    .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn closest_hit_matches_a_flat_list() {
+        for count in [1, 2, 3, 7, 64, 500] {
+            let bvh = BvhNode::new(spheres(count, count as u64));
+            let list = HittableList::from(spheres(count, count as u64));
+            let mut rng = Rng::new(99);
+            let range = Interval::new(0.001, f64::INFINITY);
+            let mut hits = 0;
+            for _ in 0..2000 {
+                let origin = random_point(&mut rng, 15.0);
+                let target = random_point(&mut rng, 10.0);
+                let ray = Ray::new(origin, target - origin);
+                let expected = list.hit(&ray, range).map(|record| record.ray_t);
+                let actual = bvh.hit(&ray, range).map(|record| record.ray_t);
+                assert_eq!(
+                    expected, actual,
+                    "{count} spheres, ray from {origin:?} to {target:?}"
+                );
+                hits += expected.is_some() as usize;
+            }
+            // the rays are aimed into the cloud, most of them should hit something
+            assert!(hits > 0, "{count} spheres");
+        }
+    }
+}
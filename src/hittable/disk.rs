@@ -0,0 +1,176 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    sampler::Sampler,
+    vec3::{Point3, Vector3},
+};
+
+use super::{HitRecord, SHADOW_ACNE_EPSILON, plane::tangent_frame};
+
+/// Flat disk of `radius` around `center`, facing `normal`.
+pub struct Disk {
+    pub center: Point3,
+    /// Unit normal on the front side
+    pub normal: Vector3,
+    pub radius: Double,
+    pub material: SharedMaterial,
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        if !ray_t_range.surrounds(t) {
+            return None;
+        }
+        let point = ray.at(t);
+        let planar = point - self.center;
+        let distance_squared = planar.len_squared();
+        if distance_squared > self.radius * self.radius {
+            return None;
+        }
+        // u is the angle around the center, v the distance from it, both in [0,1]
+        let (tangent, bitangent) = tangent_frame(self.normal);
+        let phi = planar.dot(bitangent).atan2(planar.dot(tangent)) + PI;
+        let record = HitRecord::new(ray, t, point, self.normal, self.material.as_ref())
+            .with_uv(phi / (2.0 * PI), distance_squared.sqrt() / self.radius);
+        Some(record)
+    }
+    fn bounding_box(&self) -> Aabb {
+        // along each axis the disk reaches radius * sin(angle between the axis and the normal)
+        let extent = Vector3::new(
+            [0, 1, 2].map(|n| self.radius * (1.0 - self.normal[n].powi(2)).max(0.0).sqrt()),
+        );
+        Aabb::from_points(self.center + (-extent), self.center + extent)
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> Double {
+        let ray = Ray::new(origin, direction);
+        let Some(record) = self.hit(&ray, Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY)) else {
+            return 0.0;
+        };
+        // convert the area density 1/A to solid angle: distance^2 / (|cos θ| A)
+        let distance_squared = record.ray_t * record.ray_t * direction.len_squared();
+        let cosine = (direction.dot(self.normal) / direction.len()).abs();
+        distance_squared / (cosine * PI * self.radius * self.radius)
+    }
+    /// Towards a point uniformly distributed over the area.
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let (tangent, bitangent) = tangent_frame(self.normal);
+        let [x, y, _] = Vector3::random_in_unit_disk(sampler).0;
+        let point = self.center + (tangent * x + bitangent * y) * self.radius;
+        point - origin
+    }
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vector3, radius: Double) -> Self {
+        Self {
+            center,
+            normal: normal.unit_vector(),
+            radius: radius.max(0.0),
+            material: default_material(),
+        }
+    }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.material = material;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::tests::assert_light_sampling;
+
+    fn disk() -> Disk {
+        Disk::new(
+            Point3::default().with_y(1.0),
+            Vector3::new([0.0, -3.0, 0.0]),
+            2.0,
+        )
+    }
+
+    #[test]
+    fn hits_within_the_radius() {
+        let disk = disk();
+        let range = Interval::new(0.001, f64::INFINITY);
+        let up = |x: Double, z: Double| {
+            Ray::new(Point3::new([x, 0.0, z]), Vector3::default().with_y(1.0))
+        };
+        let record = disk.hit(&up(1.2, -1.5), range).unwrap();
+        assert!((record.ray_t - 1.0).abs() < 1e-12);
+        assert!(record.normal_direction.is_outward());
+        assert_eq!(record.normal, Vector3::new([0.0, -1.0, 0.0]));
+        assert!(disk.hit(&up(1.5, -1.5), range).is_none());
+        assert!(disk.hit(&up(0.0, 0.0), Interval::new(0.001, 0.9)).is_none());
+        let down = Ray::new(
+            Point3::new([0.5, 3.0, 0.5]),
+            Vector3::default().with_y(-1.0),
+        );
+        let back = disk.hit(&down, range).unwrap();
+        assert!(!back.normal_direction.is_outward());
+        assert_eq!(back.normal, Vector3::new([0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn uv_is_angle_and_distance() {
+        let disk = disk();
+        let (tangent, bitangent) = tangent_frame(disk.normal);
+        let range = Interval::new(0.001, f64::INFINITY);
+        let mut previous = -1.0;
+        // a half turn from -π, along the tangents, at growing distance from the center
+        for (n, angle) in [-0.75, -0.5, 0.0, 0.5, 0.75].into_iter().enumerate() {
+            let angle = angle * PI;
+            let distance = 0.3 * (n + 1) as Double;
+            let target = disk.center + (tangent * angle.cos() + bitangent * angle.sin()) * distance;
+            let origin = target + Vector3::default().with_y(-1.0);
+            let record = disk.hit(&Ray::new(origin, target - origin), range).unwrap();
+            let [u, v] = record.uv;
+            assert!(
+                (u - (angle + PI) / (2.0 * PI)).abs() < 1e-9,
+                "u = {u} at {angle}"
+            );
+            assert!((v - distance / disk.radius).abs() < 1e-9);
+            assert!(u > previous);
+            previous = u;
+        }
+    }
+
+    #[test]
+    fn bounding_box_fits_a_tilted_disk() {
+        let flat = disk().bounding_box();
+        assert_eq!(
+            [flat.x.min, flat.x.max, flat.z.min, flat.z.max],
+            [-2.0, 2.0, -2.0, 2.0]
+        );
+        assert!(flat.y.contains(1.0) && flat.y.size() < 0.01);
+        // tilted 45° about z, reaching sqrt(2) along x and y
+        let tilted =
+            Disk::new(Point3::default(), Vector3::new([1.0, 1.0, 0.0]), 2.0).bounding_box();
+        let reach = 2.0_f64.sqrt();
+        for n in 0..2 {
+            assert!((tilted.axis_interval(n).max - reach).abs() < 1e-12);
+        }
+        assert!((tilted.z.max - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn light_sampling() {
+        assert_light_sampling(&disk(), Point3::new([0.5, -0.5, 0.3]));
+        assert_light_sampling(&disk(), Point3::new([3.0, 2.0, -1.0]));
+    }
+}
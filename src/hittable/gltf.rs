@@ -0,0 +1,1423 @@
+//! glTF 2.0 reader, for `.gltf` files with external or embedded buffers and binary `.glb` files.
+//!
+//! Meshes are instanced into world space through the node hierarchy. Materials are
+//! approximated from their PBR metallic-roughness factors, textures are ignored.
+
+use std::f64::consts::FRAC_PI_4;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    camera::Camera,
+    color::RGB,
+    json::{self, Value},
+    light::{LightList, directional::DirectionalLight, point::PointLight, spot::SpotLight},
+    material::{
+        SharedMaterial, default_material, dielectric::Dielectric, diffuse_light::DiffuseLight,
+        lambertian::Lambertian, metal::Metal,
+    },
+    vec3::{Point3, Vector3},
+};
+
+use super::{
+    HittableList,
+    mesh::{Face, LoadError, MeshBuffers, TriangleMesh},
+};
+
+/// Objects, camera and lights of a glTF scene.
+pub struct GltfScene {
+    pub world: HittableList,
+    /// The first camera found in the node hierarchy, if any
+    pub camera: Option<Camera>,
+    /// Lights of the `KHR_lights_punctual` extension, in world space
+    pub lights: Vec<PunctualLight>,
+}
+
+/// A light of the `KHR_lights_punctual` extension.
+#[derive(Clone, Copy, Debug)]
+pub struct PunctualLight {
+    pub kind: PunctualLightKind,
+    pub color: RGB,
+    /// Candela for point and spot lights, lux for directional lights
+    pub intensity: Double,
+    /// Distance at which the light reaches zero, unlimited if `None`
+    pub range: Option<Double>,
+    pub position: Point3,
+    /// Direction the light shines to, for spot and directional lights
+    pub direction: Vector3,
+}
+
+impl GltfScene {
+    /// The punctual lights, ready to render.
+    ///
+    /// Their intensity is used as is, so the exposure of the camera may need adjusting.
+    /// Point and spot lights fade out towards their `range`.
+    pub fn light_list(&self) -> LightList {
+        let mut list = LightList::new();
+        for light in &self.lights {
+            let intensity = light.color * light.intensity;
+            match light.kind {
+                PunctualLightKind::Point => {
+                    let mut point = PointLight::new(light.position, intensity);
+                    point.range = light.range;
+                    list.push(point)
+                }
+                PunctualLightKind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => {
+                    let mut spot = SpotLight::from_direction(
+                        light.position,
+                        light.direction,
+                        intensity,
+                        inner_cone_angle.to_degrees(),
+                        outer_cone_angle.to_degrees(),
+                    );
+                    spot.range = light.range;
+                    list.push(spot)
+                }
+                PunctualLightKind::Directional => {
+                    list.push(DirectionalLight::new(light.direction, intensity))
+                }
+            }
+        }
+        list
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PunctualLightKind {
+    Point,
+    /// Cone angles from the axis, in radians
+    Spot {
+        inner_cone_angle: Double,
+        outer_cone_angle: Double,
+    },
+    Directional,
+}
+
+/// Read the `.gltf` or `.glb` file at `path`, buffers are looked up relative to it.
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<GltfScene, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    parse_gltf(&data, path.parent()).map_err(|err| err.in_file(path))
+}
+
+/// Parse a glTF JSON document or a binary glTF container.
+///
+/// Buffers referenced by a relative uri are read from `base_dir`.
+pub fn parse_gltf(data: &[u8], base_dir: Option<&Path>) -> Result<GltfScene, LoadError> {
+    let (json, binary) = if data.starts_with(b"glTF") {
+        split_glb(data)?
+    } else {
+        (data, None)
+    };
+    let text = std::str::from_utf8(json).map_err(|_| LoadError::invalid("JSON is not utf-8"))?;
+    let json = json::parse(text).map_err(|err| LoadError::parse(err.line, err.message))?;
+    let version = json
+        .get("asset")
+        .get("version")
+        .as_str()
+        .unwrap_or_default();
+    if !version.starts_with("2.") {
+        return Err(LoadError::invalid(format!(
+            "unsupported glTF version `{}`",
+            version
+        )));
+    }
+
+    let buffers = json
+        .get("buffers")
+        .elements()
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| load_buffer(i, buffer, binary, base_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    let materials = json
+        .get("materials")
+        .elements()
+        .iter()
+        .map(material)
+        .collect();
+    let document = Document {
+        json: &json,
+        buffers,
+        materials,
+    };
+    document.scene()
+}
+
+/// The JSON and binary chunks of a `.glb` file. Errors carry the byte offset.
+fn split_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), LoadError> {
+    let word = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+            .ok_or_else(|| LoadError::parse(offset, "unexpected end of data"))
+    };
+    if word(4)? != 2 {
+        return Err(LoadError::parse(4, "unsupported binary glTF version"));
+    }
+    let length = word(8)?.min(data.len());
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = word(offset)?;
+        let chunk_type = word(offset + 4)?;
+        let start = offset + 8;
+        let chunk = data
+            .get(start..start + chunk_length)
+            .ok_or_else(|| LoadError::parse(offset, "chunk exceeds the file"))?;
+        match chunk_type {
+            // "JSON" and "BIN\0"
+            0x4e4f_534a if json.is_none() => json = Some(chunk),
+            0x004e_4942 if binary.is_none() => binary = Some(chunk),
+            _ => {}
+        }
+        // chunks are 4 byte aligned
+        offset = start + chunk_length.next_multiple_of(4);
+    }
+    let json = json.ok_or_else(|| LoadError::parse(12, "missing JSON chunk"))?;
+    Ok((json, binary))
+}
+
+fn load_buffer(
+    index: usize,
+    buffer: &Value,
+    binary: Option<&[u8]>,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>, LoadError> {
+    let data = match buffer.get("uri").as_str() {
+        Some(uri) if uri.starts_with("data:") => {
+            let (_, encoded) = uri.split_once(";base64,").ok_or_else(|| {
+                LoadError::invalid(format!("buffer {}: data uri is not base64", index))
+            })?;
+            decode_base64(encoded)
+                .ok_or_else(|| LoadError::invalid(format!("buffer {}: invalid base64", index)))?
+        }
+        Some(uri) => {
+            let file_name = decode_uri(uri);
+            let path = match base_dir {
+                Some(dir) => dir.join(file_name),
+                None => file_name.into(),
+            };
+            fs::read(path)?
+        }
+        // only the first buffer may refer to the binary chunk
+        None if index == 0 => binary
+            .ok_or_else(|| LoadError::invalid("buffer 0 has no uri and there is no binary chunk"))?
+            .to_vec(),
+        None => return Err(LoadError::invalid(format!("buffer {} has no uri", index))),
+    };
+    let length = buffer.get("byteLength").as_usize().unwrap_or(data.len());
+    if data.len() < length {
+        return Err(LoadError::invalid(format!(
+            "buffer {} is shorter than its byteLength",
+            index
+        )));
+    }
+    Ok(data)
+}
+
+/// The parsed document, with its buffers loaded.
+struct Document<'a> {
+    json: &'a Value,
+    buffers: Vec<Vec<u8>>,
+    materials: Vec<SharedMaterial>,
+}
+
+impl Document<'_> {
+    fn scene(&self) -> Result<GltfScene, LoadError> {
+        let mut scene = GltfScene {
+            world: HittableList::new(),
+            camera: None,
+            lights: Vec::new(),
+        };
+        let nodes = self.json.get("nodes").elements();
+        let scenes = self.json.get("scenes");
+        let roots: Vec<usize> = match scenes.at(self.json.get("scene").as_usize().unwrap_or(0)) {
+            Value::Null => {
+                // without scenes, every node which is nobody's child is a root
+                let children: Vec<usize> = nodes
+                    .iter()
+                    .flat_map(|node| node.get("children").elements())
+                    .filter_map(Value::as_usize)
+                    .collect();
+                (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+            }
+            scene => scene
+                .get("nodes")
+                .elements()
+                .iter()
+                .filter_map(Value::as_usize)
+                .collect(),
+        };
+
+        // depth first, so the first camera is the one a viewer would pick
+        let mut stack: Vec<(usize, Matrix)> = roots
+            .into_iter()
+            .rev()
+            .map(|root| (root, IDENTITY))
+            .collect();
+        let mut visited = 0;
+        while let Some((index, parent)) = stack.pop() {
+            visited += 1;
+            if visited > nodes.len() {
+                return Err(LoadError::invalid("node hierarchy has a cycle"));
+            }
+            let node = nodes
+                .get(index)
+                .ok_or_else(|| LoadError::invalid(format!("node {} does not exist", index)))?;
+            let transform = mul(&parent, &local_transform(node));
+            if let Some(mesh) = node.get("mesh").as_usize()
+                && let Some(buffers) = self.mesh(mesh, &transform)?
+            {
+                scene.world.push(TriangleMesh::new(buffers)?);
+            }
+            if let Some(camera) = node.get("camera").as_usize()
+                && scene.camera.is_none()
+            {
+                scene.camera = self.camera(camera, &transform)?;
+            }
+            let light = node
+                .get("extensions")
+                .get("KHR_lights_punctual")
+                .get("light");
+            if let Some(light) = light.as_usize() {
+                scene.lights.push(self.light(light, &transform)?);
+            }
+            for child in node.get("children").elements().iter().rev() {
+                let child = child
+                    .as_usize()
+                    .ok_or_else(|| LoadError::invalid(format!("node {}: invalid child", index)))?;
+                stack.push((child, transform));
+            }
+        }
+        Ok(scene)
+    }
+
+    /// All triangle primitives of mesh `index`, transformed into world space.
+    fn mesh(&self, index: usize, transform: &Matrix) -> Result<Option<MeshBuffers>, LoadError> {
+        let mesh = self.json.get("meshes").at(index);
+        if mesh.is_null() {
+            return Err(LoadError::invalid(format!("mesh {} does not exist", index)));
+        }
+        let normal_transform = normal_matrix(transform);
+        // a mirroring transform turns the winding around
+        let mirrored = determinant3(transform) < 0.0;
+        let mut buffers = MeshBuffers::default();
+        let mut has_colors = false;
+        let mut default_material_index = None;
+        for (p, primitive) in mesh.get("primitives").elements().iter().enumerate() {
+            let error = |message: &str| {
+                LoadError::invalid(format!("mesh {} primitive {}: {}", index, p, message))
+            };
+            let mode = primitive.get("mode").as_usize().unwrap_or(4);
+            // points and lines have no surface
+            if !(4..=6).contains(&mode) {
+                continue;
+            }
+            let attributes = primitive.get("attributes");
+            let positions = attributes
+                .get("POSITION")
+                .as_usize()
+                .ok_or_else(|| error("missing POSITION"))?;
+            let positions = self.accessor(positions)?;
+            let vertex_count = positions.count();
+            let first_vertex = buffers.positions.len() as u32;
+            buffers.positions.extend(
+                positions
+                    .rows()
+                    .map(|p| transform_point(transform, [p[0], p[1], p[2]])),
+            );
+
+            let first_normal = buffers.normals.len() as u32;
+            let normals = match attributes.get("NORMAL").as_usize() {
+                Some(accessor) => {
+                    let normals = self.accessor(accessor)?;
+                    if normals.count() != vertex_count {
+                        return Err(error("NORMAL and POSITION counts differ"));
+                    }
+                    buffers.normals.extend(normals.rows().map(|n| {
+                        let normal = transform_vector(&normal_transform, [n[0], n[1], n[2]]);
+                        if normal.near_zero() {
+                            normal
+                        } else {
+                            normal.unit_vector()
+                        }
+                    }));
+                    true
+                }
+                None => false,
+            };
+            let first_uv = buffers.uvs.len() as u32;
+            let uvs = match attributes.get("TEXCOORD_0").as_usize() {
+                Some(accessor) => {
+                    let uvs = self.accessor(accessor)?;
+                    if uvs.count() != vertex_count {
+                        return Err(error("TEXCOORD_0 and POSITION counts differ"));
+                    }
+                    // glTF puts v = 0 at the top of the image
+                    buffers
+                        .uvs
+                        .extend(uvs.rows().map(|uv| [uv[0], 1.0 - uv[1]]));
+                    true
+                }
+                None => false,
+            };
+            // colors are indexed like the positions, so primitives without them are white
+            match attributes.get("COLOR_0").as_usize() {
+                Some(accessor) => {
+                    let colors = self.accessor(accessor)?;
+                    if colors.count() != vertex_count {
+                        return Err(error("COLOR_0 and POSITION counts differ"));
+                    }
+                    buffers
+                        .colors
+                        .extend(colors.rows().map(|c| RGB::new([c[0], c[1], c[2]])));
+                    has_colors = true;
+                }
+                None => buffers
+                    .colors
+                    .extend(std::iter::repeat_n(RGB::new([1.0; 3]), vertex_count)),
+            }
+
+            let material = match primitive.get("material").as_usize() {
+                Some(material) => {
+                    let shared = self
+                        .materials
+                        .get(material)
+                        .ok_or_else(|| error("material does not exist"))?;
+                    buffers.materials.push(shared.clone());
+                    buffers.materials.len() as u32 - 1
+                }
+                None => *default_material_index.get_or_insert_with(|| {
+                    buffers.materials.push(default_material());
+                    buffers.materials.len() as u32 - 1
+                }),
+            };
+
+            let indices: Vec<u32> = match primitive.get("indices").as_usize() {
+                Some(accessor) => self
+                    .accessor(accessor)?
+                    .values
+                    .iter()
+                    .map(|&i| i as u32)
+                    .collect(),
+                None => (0..vertex_count as u32).collect(),
+            };
+            if let Some(&i) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+                return Err(error(&format!("vertex index {} out of range", i)));
+            }
+            let triangles: Vec<[u32; 3]> = match mode {
+                4 => indices
+                    .chunks_exact(3)
+                    .map(|t| [t[0], t[1], t[2]])
+                    .collect(),
+                // strips alternate their winding
+                5 => indices
+                    .windows(3)
+                    .enumerate()
+                    .map(|(i, t)| {
+                        if i % 2 == 0 {
+                            [t[0], t[1], t[2]]
+                        } else {
+                            [t[1], t[0], t[2]]
+                        }
+                    })
+                    .collect(),
+                _ => indices
+                    .windows(2)
+                    .skip(1)
+                    .map(|t| [indices[0], t[0], t[1]])
+                    .collect(),
+            };
+            for [a, b, c] in triangles {
+                let corners = if mirrored { [a, c, b] } else { [a, b, c] };
+                buffers.faces.push(Face {
+                    positions: corners.map(|i| first_vertex + i),
+                    normals: normals.then(|| corners.map(|i| first_normal + i)),
+                    uvs: uvs.then(|| corners.map(|i| first_uv + i)),
+                    material,
+                    group: 0,
+                });
+            }
+        }
+        if !has_colors {
+            buffers.colors.clear();
+        }
+        if buffers.faces.is_empty() {
+            return Ok(None);
+        }
+        if let Some(name) = mesh.get("name").as_str() {
+            buffers.groups.push(name.to_string());
+        }
+        Ok(Some(buffers))
+    }
+
+    /// A perspective camera placed by `transform`, orthographic cameras are not supported.
+    fn camera(&self, index: usize, transform: &Matrix) -> Result<Option<Camera>, LoadError> {
+        let camera = self.json.get("cameras").at(index);
+        if camera.is_null() {
+            return Err(LoadError::invalid(format!(
+                "camera {} does not exist",
+                index
+            )));
+        }
+        let perspective = camera.get("perspective");
+        let Some(yfov) = perspective.get("yfov").as_f64() else {
+            return Ok(None);
+        };
+        // the camera looks down its -z axis, with +y up
+        let look_from = transform_point(transform, [0.0; 3]);
+        let forward = transform_vector(transform, [0.0, 0.0, -1.0]).unit_vector();
+        let mut camera = Camera::default()
+            .with_vfov(yfov.to_degrees())
+            .with_look_from(look_from)
+            .with_look_at(look_from + forward)
+            .with_vup(transform_vector(transform, [0.0, 1.0, 0.0]));
+        if let Some(aspect_ratio) = perspective.get("aspectRatio").as_f64() {
+            camera.aspect_ratio = aspect_ratio;
+        }
+        Ok(Some(camera))
+    }
+
+    fn light(&self, index: usize, transform: &Matrix) -> Result<PunctualLight, LoadError> {
+        let light = self
+            .json
+            .get("extensions")
+            .get("KHR_lights_punctual")
+            .get("lights")
+            .at(index);
+        let error = |message: &str| LoadError::invalid(format!("light {}: {}", index, message));
+        let kind = match light.get("type").as_str() {
+            Some("point") => PunctualLightKind::Point,
+            Some("spot") => {
+                let spot = light.get("spot");
+                PunctualLightKind::Spot {
+                    inner_cone_angle: spot.get("innerConeAngle").as_f64().unwrap_or(0.0),
+                    outer_cone_angle: spot.get("outerConeAngle").as_f64().unwrap_or(FRAC_PI_4),
+                }
+            }
+            Some("directional") => PunctualLightKind::Directional,
+            Some(_) => return Err(error("unknown type")),
+            None if light.is_null() => return Err(error("does not exist")),
+            None => return Err(error("missing type")),
+        };
+        // lights shine down their -z axis
+        let direction = transform_vector(transform, [0.0, 0.0, -1.0]);
+        Ok(PunctualLight {
+            kind,
+            color: RGB::new(light.get("color").as_numbers().unwrap_or([1.0; 3])),
+            intensity: light.get("intensity").as_f64().unwrap_or(1.0),
+            range: light.get("range").as_f64(),
+            position: transform_point(transform, [0.0; 3]),
+            direction: if direction.near_zero() {
+                direction
+            } else {
+                direction.unit_vector()
+            },
+        })
+    }
+
+    /// The elements of accessor `index` as floats, normalized integers are mapped to [0,1] or [-1,1].
+    fn accessor(&self, index: usize) -> Result<Accessor, LoadError> {
+        let accessor = self.json.get("accessors").at(index);
+        let error = |message: &str| LoadError::invalid(format!("accessor {}: {}", index, message));
+        if accessor.is_null() {
+            return Err(error("does not exist"));
+        }
+        let components = match accessor.get("type").as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(error("invalid type")),
+        };
+        let component_type = accessor.get("componentType").as_usize();
+        let component_type =
+            ComponentType::new(component_type).ok_or_else(|| error("invalid componentType"))?;
+        let count = accessor
+            .get("count")
+            .as_usize()
+            .ok_or_else(|| error("missing count"))?;
+        let normalized = accessor.get("normalized").as_bool().unwrap_or(false);
+        let offset = accessor.get("byteOffset").as_usize().unwrap_or(0);
+
+        // an accessor without a buffer view is all zeros, until sparse values replace some
+        let mut values = match accessor.get("bufferView").as_usize() {
+            Some(view) => {
+                let layout = Layout {
+                    component_type,
+                    components,
+                    normalized,
+                };
+                self.read_view(view, offset, count, layout)
+                    .map_err(|message| error(&message))?
+            }
+            None => {
+                // such an accessor takes no space in the file, so it may not be larger
+                // than the buffers to keep a tiny file from allocating gigabytes
+                let buffer_size: usize = self.buffers.iter().map(Vec::len).sum();
+                count
+                    .checked_mul(component_type.size() * components)
+                    .filter(|&size| size <= buffer_size)
+                    .ok_or_else(|| error("count exceeds the buffers"))?;
+                vec![0.0; count * components]
+            }
+        };
+
+        let sparse = accessor.get("sparse");
+        if !sparse.is_null() {
+            let sparse_count = sparse
+                .get("count")
+                .as_usize()
+                .ok_or_else(|| error("sparse count missing"))?;
+            let indices = sparse.get("indices");
+            let index_layout = Layout {
+                component_type: ComponentType::new(indices.get("componentType").as_usize())
+                    .ok_or_else(|| error("invalid sparse index componentType"))?,
+                components: 1,
+                normalized: false,
+            };
+            let targets = self
+                .read_view(
+                    indices.get("bufferView").as_usize().unwrap_or(usize::MAX),
+                    indices.get("byteOffset").as_usize().unwrap_or(0),
+                    sparse_count,
+                    index_layout,
+                )
+                .map_err(|message| error(&message))?;
+            let sparse_values = sparse.get("values");
+            let replacements = self
+                .read_view(
+                    sparse_values
+                        .get("bufferView")
+                        .as_usize()
+                        .unwrap_or(usize::MAX),
+                    sparse_values.get("byteOffset").as_usize().unwrap_or(0),
+                    sparse_count,
+                    Layout {
+                        component_type,
+                        components,
+                        normalized,
+                    },
+                )
+                .map_err(|message| error(&message))?;
+            for (target, replacement) in targets.iter().zip(replacements.chunks_exact(components)) {
+                let target = *target as usize;
+                if target >= count {
+                    return Err(error("sparse index out of range"));
+                }
+                values[target * components..(target + 1) * components].copy_from_slice(replacement);
+            }
+        }
+        Ok(Accessor { components, values })
+    }
+
+    /// Read `count` elements of buffer view `index`, starting `offset` bytes into the view.
+    fn read_view(
+        &self,
+        index: usize,
+        offset: usize,
+        count: usize,
+        layout: Layout,
+    ) -> Result<Vec<Double>, String> {
+        let view = self.json.get("bufferViews").at(index);
+        if view.is_null() {
+            return Err(format!("buffer view {} does not exist", index));
+        }
+        let buffer = view
+            .get("buffer")
+            .as_usize()
+            .and_then(|buffer| self.buffers.get(buffer))
+            .ok_or_else(|| format!("buffer view {}: invalid buffer", index))?;
+        let view_offset = view.get("byteOffset").as_usize().unwrap_or(0);
+        let view_length = view.get("byteLength").as_usize().unwrap_or(0);
+        let size = layout.component_type.size();
+        let element_size = size * layout.components;
+        let stride = view.get("byteStride").as_usize().unwrap_or(element_size);
+        if stride < element_size {
+            return Err(format!(
+                "buffer view {}: byteStride is below the element size",
+                index
+            ));
+        }
+        let bytes = view_offset
+            .checked_add(view_length)
+            .and_then(|end| buffer.get(view_offset..end))
+            .ok_or_else(|| format!("buffer view {} exceeds its buffer", index))?;
+        // the last element needs only its own size, not a whole stride
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|start| start.checked_add(offset))
+                .and_then(|start| start.checked_add(element_size)),
+            None => Some(offset),
+        };
+        if !end.is_some_and(|end| end <= bytes.len()) {
+            return Err(format!("elements exceed buffer view {}", index));
+        }
+        let mut values = Vec::with_capacity(count * layout.components);
+        for element in 0..count {
+            let start = offset + element * stride;
+            for component in 0..layout.components {
+                let at = start + component * size;
+                values.push(layout.read(&bytes[at..at + size]));
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Flattened elements of an accessor.
+struct Accessor {
+    components: usize,
+    values: Vec<Double>,
+}
+
+impl Accessor {
+    fn count(&self) -> usize {
+        self.values.len() / self.components
+    }
+    /// Elements shorter than 3 components are padded with zeros.
+    fn rows(&self) -> impl Iterator<Item = [Double; 4]> + '_ {
+        self.values.chunks_exact(self.components).map(|row| {
+            let mut padded = [0.0; 4];
+            for (value, component) in padded.iter_mut().zip(row) {
+                *value = *component;
+            }
+            padded
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ComponentType {
+    I8,
+    U8,
+    I16,
+    U16,
+    U32,
+    F32,
+}
+
+impl ComponentType {
+    fn new(code: Option<usize>) -> Option<Self> {
+        Some(match code? {
+            5120 => ComponentType::I8,
+            5121 => ComponentType::U8,
+            5122 => ComponentType::I16,
+            5123 => ComponentType::U16,
+            5125 => ComponentType::U32,
+            5126 => ComponentType::F32,
+            _ => return None,
+        })
+    }
+    fn size(self) -> usize {
+        match self {
+            ComponentType::I8 | ComponentType::U8 => 1,
+            ComponentType::I16 | ComponentType::U16 => 2,
+            ComponentType::U32 | ComponentType::F32 => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    component_type: ComponentType,
+    components: usize,
+    normalized: bool,
+}
+
+impl Layout {
+    /// Decode one little endian component.
+    fn read(&self, bytes: &[u8]) -> Double {
+        let (value, max) = match self.component_type {
+            ComponentType::I8 => (bytes[0] as i8 as Double, i8::MAX as Double),
+            ComponentType::U8 => (bytes[0] as Double, u8::MAX as Double),
+            ComponentType::I16 => (
+                i16::from_le_bytes([bytes[0], bytes[1]]) as Double,
+                i16::MAX as Double,
+            ),
+            ComponentType::U16 => (
+                u16::from_le_bytes([bytes[0], bytes[1]]) as Double,
+                u16::MAX as Double,
+            ),
+            ComponentType::U32 => (
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as Double,
+                u32::MAX as Double,
+            ),
+            ComponentType::F32 => {
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                return value as Double;
+            }
+        };
+        if self.normalized {
+            (value / max).max(-1.0)
+        } else {
+            value
+        }
+    }
+}
+
+/// Closest material of this renderer to a PBR metallic-roughness material.
+fn material(json: &Value) -> SharedMaterial {
+    let pbr = json.get("pbrMetallicRoughness");
+    let [r, g, b, _] = pbr.get("baseColorFactor").as_numbers().unwrap_or([1.0; 4]);
+    let base_color = RGB::new([r, g, b]);
+    let metallic = pbr.get("metallicFactor").as_f64().unwrap_or(1.0);
+    let roughness = pbr.get("roughnessFactor").as_f64().unwrap_or(1.0);
+    let extensions = json.get("extensions");
+    let transmission = extensions
+        .get("KHR_materials_transmission")
+        .get("transmissionFactor")
+        .as_f64()
+        .unwrap_or(0.0);
+    let ior = extensions
+        .get("KHR_materials_ior")
+        .get("ior")
+        .as_f64()
+        .unwrap_or(1.5);
+    let strength = extensions
+        .get("KHR_materials_emissive_strength")
+        .get("emissiveStrength")
+        .as_f64()
+        .unwrap_or(1.0);
+    let emissive = RGB::new(json.get("emissiveFactor").as_numbers().unwrap_or([0.0; 3])) * strength;
+    // emitters do not reflect in this renderer
+    if emissive.r().max(emissive.g()).max(emissive.b()) > 0.0 {
+        let two_sided = json.get("doubleSided").as_bool().unwrap_or(false);
+        Arc::new(DiffuseLight::new(emissive).with_two_sided(two_sided))
+    } else if transmission >= 0.5 {
+        Arc::new(Dielectric::new(ior))
+    } else if metallic >= 0.5 {
+        Arc::new(Metal::new(base_color, roughness))
+    } else {
+        Arc::new(Lambertian::new(base_color))
+    }
+}
+
+/// Column major 4x4 matrix, `m[column][row]` as in glTF.
+type Matrix = [[Double; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut m = [[0.0; 4]; 4];
+    for (column, b_column) in m.iter_mut().zip(b) {
+        for (row, value) in column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+        }
+    }
+    m
+}
+
+/// The `matrix` of a node, or its translation * rotation * scale.
+fn local_transform(node: &Value) -> Matrix {
+    if let Some(m) = node.get("matrix").as_numbers::<16>() {
+        return [0, 1, 2, 3].map(|column| [0, 1, 2, 3].map(|row| m[column * 4 + row]));
+    }
+    let [tx, ty, tz] = node.get("translation").as_numbers().unwrap_or([0.0; 3]);
+    let [x, y, z, w] = node
+        .get("rotation")
+        .as_numbers()
+        .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node.get("scale").as_numbers().unwrap_or([1.0; 3]);
+    // columns of the rotation matrix of the unit quaternion (x, y, z, w), scaled
+    [
+        [
+            (1.0 - 2.0 * (y * y + z * z)) * sx,
+            2.0 * (x * y + z * w) * sx,
+            2.0 * (x * z - y * w) * sx,
+            0.0,
+        ],
+        [
+            2.0 * (x * y - z * w) * sy,
+            (1.0 - 2.0 * (x * x + z * z)) * sy,
+            2.0 * (y * z + x * w) * sy,
+            0.0,
+        ],
+        [
+            2.0 * (x * z + y * w) * sz,
+            2.0 * (y * z - x * w) * sz,
+            (1.0 - 2.0 * (x * x + y * y)) * sz,
+            0.0,
+        ],
+        [tx, ty, tz, 1.0],
+    ]
+}
+
+fn transform_point(m: &Matrix, p: [Double; 3]) -> Point3 {
+    Point3::new(
+        [0, 1, 2].map(|row| m[0][row] * p[0] + m[1][row] * p[1] + m[2][row] * p[2] + m[3][row]),
+    )
+}
+
+fn transform_vector(m: &Matrix, v: [Double; 3]) -> Vector3 {
+    Vector3::new([0, 1, 2].map(|row| m[0][row] * v[0] + m[1][row] * v[1] + m[2][row] * v[2]))
+}
+
+fn determinant3(m: &Matrix) -> Double {
+    m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+        - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+        + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+}
+
+/// Transforms normals: the cofactor matrix of the upper 3x3, which is the
+/// inverse transpose up to the determinant, and normals are normalized anyway.
+fn normal_matrix(m: &Matrix) -> Matrix {
+    let c = |column: usize, row: usize| {
+        // the 2x2 minor without `column` and `row`, both taken cyclically
+        let (c1, c2) = ((column + 1) % 3, (column + 2) % 3);
+        let (r1, r2) = ((row + 1) % 3, (row + 2) % 3);
+        m[c1][r1] * m[c2][r2] - m[c2][r1] * m[c1][r2]
+    };
+    let sign = if determinant3(m) < 0.0 { -1.0 } else { 1.0 };
+    let mut normal = IDENTITY;
+    for (column, values) in normal.iter_mut().take(3).enumerate() {
+        for (row, value) in values.iter_mut().take(3).enumerate() {
+            *value = sign * c(column, row);
+        }
+    }
+    normal
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for ch in encoded.bytes().take_while(|&ch| ch != b'=') {
+        let value = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = ((bits << 6) | value as u32) & 0xff_ffff;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Undo the percent encoding of a relative uri.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| uri.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittable::Hittable, interval::Interval, material::tests::classify, ray::Ray};
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut encoded = String::new();
+        for chunk in bytes.chunks(3) {
+            let word = chunk.iter().enumerate().fold(0u32, |word, (i, &byte)| {
+                word | (byte as u32) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    encoded.push(ALPHABET[(word >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    /// The triangle (0,0,0), (1,0,0), (0,1,0), facing +z.
+    const TRIANGLE: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    const ACCESSOR: &str =
+        r#"{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}"#;
+    const VIEW: &str = r#"{"buffer": 0, "byteLength": 36}"#;
+    const MESH: &str = r#""meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}]"#;
+    const NODE: &str = r#""nodes": [{"mesh": 0}]"#;
+
+    /// A document with the triangle in an embedded buffer, read by the position
+    /// `accessor` through `view`, and the other top level `members`.
+    fn document(accessor: &str, view: &str, members: &str) -> String {
+        format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "accessors": [{}],
+  "bufferViews": [{}],
+  "buffers": [{{"byteLength": 36, "uri": "data:application/octet-stream;base64,{}"}}],
+  {}
+}}"#,
+            accessor,
+            view,
+            encode_base64(&floats(&TRIANGLE)),
+            members
+        )
+    }
+
+    /// The triangle placed by the given `nodes`, with the other top level `members`.
+    fn scene(nodes: &str, members: &str) -> GltfScene {
+        let members = format!(r#""nodes": [{}], {}, {}"#, nodes, MESH, members);
+        parse_gltf(document(ACCESSOR, VIEW, &members).as_bytes(), None).unwrap()
+    }
+
+    fn load(accessor: &str, view: &str) -> Result<GltfScene, LoadError> {
+        let members = format!("{}, {}", NODE, MESH);
+        parse_gltf(document(accessor, view, &members).as_bytes(), None)
+    }
+
+    /// Where a ray hits the world, and whether it hits the front side.
+    fn trace(
+        scene: &GltfScene,
+        origin: [Double; 3],
+        direction: [Double; 3],
+    ) -> Option<([Double; 3], bool)> {
+        let ray = Ray::new(Point3::new(origin), Vector3::new(direction));
+        let record = scene
+            .world
+            .hit(&ray, Interval::new(0.001, Double::INFINITY))?;
+        Some((record.point.0, record.normal_direction.is_outward()))
+    }
+
+    fn assert_close(a: [Double; 3], b: [Double; 3]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    const DOWN: [Double; 3] = [0.0, 0.0, -1.0];
+
+    #[test]
+    fn base64_round_trip() {
+        for text in [&b""[..], b"f", b"fo", b"foo", b"foob"] {
+            assert_eq!(decode_base64(&encode_base64(text)).unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn embedded_triangle() {
+        let scene = load(ACCESSOR, VIEW).unwrap();
+        let (point, front) = trace(&scene, [0.25, 0.25, 1.0], DOWN).unwrap();
+        assert_close(point, [0.25, 0.25, 0.0]);
+        assert!(front);
+        assert!(trace(&scene, [0.75, 0.75, 1.0], DOWN).is_none());
+    }
+
+    #[test]
+    fn node_transforms() {
+        // the translation of the parent applies after the scale of the child
+        let scene = scene(
+            r#"{"translation": [0, 0, -2], "children": [1]}, {"mesh": 0, "scale": [2, 2, 2]}"#,
+            r#""scene": 0, "scenes": [{"nodes": [0]}]"#,
+        );
+        let (point, _) = trace(&scene, [1.5, 0.25, 5.0], DOWN).unwrap();
+        assert_close(point, [1.5, 0.25, -2.0]);
+        assert!(trace(&scene, [2.5, 0.1, 5.0], DOWN).is_none());
+
+        // a quarter turn around y takes x to -z
+        let half = 0.5_f64.sqrt();
+        let rotated = format!(r#"{{"mesh": 0, "rotation": [0, {half}, 0, {half}]}}"#);
+        let scene = self::scene(&rotated, r#""scenes": []"#);
+        let (point, _) = trace(&scene, [5.0, 0.25, -0.25], [-1.0, 0.0, 0.0]).unwrap();
+        assert_close(point, [0.0, 0.25, -0.25]);
+        assert!(trace(&scene, [5.0, 0.25, 0.25], [-1.0, 0.0, 0.0]).is_none());
+
+        // scale, then rotate a quarter turn around z, then translate
+        let trs = format!(
+            r#"{{"mesh": 0, "translation": [0, 0, -1], "rotation": [0, 0, {half}, {half}], "scale": [2, 1, 1]}}"#
+        );
+        let scene = self::scene(&trs, r#""scenes": []"#);
+        let (point, _) = trace(&scene, [-0.25, 1.0, 5.0], DOWN).unwrap();
+        assert_close(point, [-0.25, 1.0, -1.0]);
+        assert!(trace(&scene, [0.25, 0.25, 5.0], DOWN).is_none());
+
+        // a column major matrix translating along x
+        let scene = self::scene(
+            r#"{"mesh": 0, "matrix": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 3, 0, 0, 1]}"#,
+            r#""scenes": []"#,
+        );
+        let (point, _) = trace(&scene, [3.25, 0.25, 5.0], DOWN).unwrap();
+        assert_close(point, [3.25, 0.25, 0.0]);
+        assert!(trace(&scene, [0.25, 0.25, 5.0], DOWN).is_none());
+    }
+
+    #[test]
+    fn mirroring_keeps_the_front_side() {
+        // a mirror along x turns the winding around, the triangle still faces +z
+        for node in [
+            r#"{"mesh": 0, "scale": [-1, 1, 1]}"#,
+            r#"{"mesh": 0, "matrix": [-1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]}"#,
+        ] {
+            let scene = scene(node, r#""scenes": []"#);
+            let (point, front) = trace(&scene, [-0.25, 0.25, 5.0], DOWN).unwrap();
+            assert_close(point, [-0.25, 0.25, 0.0]);
+            assert!(front, "{node}");
+        }
+        // two mirrors make a half turn around x, the triangle faces -z
+        let scene = scene(
+            r#"{"scale": [1, -1, 1], "children": [1]}, {"mesh": 0, "scale": [1, 1, -1]}"#,
+            r#""scenes": []"#,
+        );
+        let (_, front) = trace(&scene, [0.25, -0.25, -5.0], [0.0, 0.0, 1.0]).unwrap();
+        assert!(front);
+        let (_, front) = trace(&scene, [0.25, -0.25, 5.0], DOWN).unwrap();
+        assert!(!front);
+    }
+
+    /// A binary glTF container with the given chunks.
+    fn glb(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, data) in chunks {
+            let mut data = data.to_vec();
+            data.resize(data.len().next_multiple_of(4), b' ');
+            body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            body.extend_from_slice(*kind);
+            body.extend_from_slice(&data);
+        }
+        let mut file = b"glTF".to_vec();
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&(12 + body.len() as u32).to_le_bytes());
+        file.extend_from_slice(&body);
+        file
+    }
+
+    #[test]
+    fn binary_container() {
+        let json = format!(
+            r#"{{"asset": {{"version": "2.0"}}, {}, {}, "accessors": [{}], "bufferViews": [{}], "buffers": [{{"byteLength": 36}}]}}"#,
+            NODE, MESH, ACCESSOR, VIEW
+        );
+        let binary = floats(&TRIANGLE);
+        let file = glb(&[
+            (b"JSON", json.as_bytes()),
+            (b"XTRA", b"skipped"),
+            (b"BIN\0", &binary),
+        ]);
+        let scene = parse_gltf(&file, None).unwrap();
+        let (point, _) = trace(&scene, [0.25, 0.25, 1.0], DOWN).unwrap();
+        assert_close(point, [0.25, 0.25, 0.0]);
+
+        // the binary chunk is missing or cut short
+        let without_binary = glb(&[(b"JSON", json.as_bytes())]);
+        assert!(parse_gltf(&without_binary, None).is_err());
+        let mut truncated = file.clone();
+        truncated.truncate(file.len() - 4);
+        truncated[8..12].copy_from_slice(&(file.len() as u32).to_le_bytes());
+        assert!(matches!(
+            parse_gltf(&truncated, None),
+            Err(LoadError::Parse { .. })
+        ));
+        // no JSON chunk, or another version
+        assert!(parse_gltf(&glb(&[(b"BIN\0", &binary)]), None).is_err());
+        let mut version = file;
+        version[4] = 1;
+        assert!(matches!(
+            parse_gltf(&version, None),
+            Err(LoadError::Parse { line: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn first_perspective_camera() {
+        let half = 0.5_f64.sqrt();
+        let cameras = r#""cameras": [
+            {"type": "perspective", "perspective": {"yfov": 0.5, "aspectRatio": 2.0, "znear": 0.1}},
+            {"type": "orthographic", "orthographic": {"xmag": 1, "ymag": 1, "znear": 0.1, "zfar": 10}},
+            {"type": "perspective", "perspective": {"yfov": 1.0, "znear": 0.1}}
+        ]"#;
+        let camera = |nodes: &str| scene(nodes, cameras).camera;
+        let nodes = format!(
+            r#"{{"children": [1, 2]}}, {{"camera": 0, "translation": [0, 1, 5], "rotation": [0, {half}, 0, {half}]}}, {{"camera": 2}}"#
+        );
+        let first = camera(&nodes).unwrap();
+        assert_close(first.look_from.0, [0.0, 1.0, 5.0]);
+        // the camera looks down -z, turned a quarter around y
+        assert_close(first.look_at.0, [-1.0, 1.0, 5.0]);
+        assert_close(first.vup.0, [0.0, 1.0, 0.0]);
+        assert!((first.vfov - 0.5_f64.to_degrees()).abs() < 1e-9);
+        assert_eq!(first.aspect_ratio, 2.0);
+
+        // orthographic cameras are skipped
+        let nodes =
+            r#"{"children": [1, 2]}, {"camera": 1}, {"camera": 2, "translation": [0, 0, 3]}"#;
+        let perspective = camera(nodes).unwrap();
+        assert_close(perspective.look_from.0, [0.0, 0.0, 3.0]);
+        assert_close(perspective.look_at.0, [0.0, 0.0, 2.0]);
+        assert!((perspective.vfov - 1.0_f64.to_degrees()).abs() < 1e-9);
+        assert!(camera(r#"{"camera": 1}"#).is_none());
+        assert!(camera(r#"{"mesh": 0}"#).is_none());
+
+        let missing = format!(r#""nodes": [{{"camera": 3}}], {}"#, cameras);
+        assert!(parse_gltf(document(ACCESSOR, VIEW, &missing).as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn punctual_lights() {
+        let half = 0.5_f64.sqrt();
+        let light = |index: usize| {
+            format!(r#""extensions": {{"KHR_lights_punctual": {{"light": {index}}}}}"#)
+        };
+        let nodes = format!(
+            r#"{{"translation": [1, 2, 3], {}}}, {{"rotation": [{half}, 0, 0, {half}], {}}}, {{{}}}"#,
+            light(0),
+            light(1),
+            light(2)
+        );
+        let lights = r#""extensions": {"KHR_lights_punctual": {"lights": [
+            {"type": "point", "color": [1, 0.5, 0.25], "intensity": 10, "range": 4},
+            {"type": "spot", "spot": {"outerConeAngle": 0.5}},
+            {"type": "directional", "intensity": 3}
+        ]}}"#;
+        let scene = scene(&nodes, lights);
+        let [point, spot, directional] = &scene.lights[..] else {
+            panic!("expected 3 lights, got {}", scene.lights.len());
+        };
+        assert!(matches!(point.kind, PunctualLightKind::Point));
+        assert_eq!(point.color.0, [1.0, 0.5, 0.25]);
+        assert_eq!(point.intensity, 10.0);
+        assert_eq!(point.range, Some(4.0));
+        assert_close(point.position.0, [1.0, 2.0, 3.0]);
+
+        let PunctualLightKind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } = spot.kind
+        else {
+            panic!("expected a spot light");
+        };
+        assert_eq!((inner_cone_angle, outer_cone_angle), (0.0, 0.5));
+        assert_eq!(
+            (spot.color.0, spot.intensity, spot.range),
+            ([1.0; 3], 1.0, None)
+        );
+        // -z turned a quarter around x
+        assert_close(spot.direction.0, [0.0, 1.0, 0.0]);
+
+        assert!(matches!(directional.kind, PunctualLightKind::Directional));
+        assert_eq!(directional.intensity, 3.0);
+        assert_close(directional.direction.0, [0.0, 0.0, -1.0]);
+
+        for lights in [
+            r#""extensions": {"KHR_lights_punctual": {"lights": [{"type": "area"}]}}"#,
+            r#""extensions": {"KHR_lights_punctual": {"lights": [{"color": [1, 1, 1]}]}}"#,
+            r#""extensions": {"KHR_lights_punctual": {"lights": []}}"#,
+        ] {
+            let members = format!(r#""nodes": [{{{}}}], {}"#, light(0), lights);
+            assert!(parse_gltf(document(ACCESSOR, VIEW, &members).as_bytes(), None).is_err());
+        }
+    }
+
+    #[test]
+    fn light_list_fades_out_at_the_range() {
+        let light = |index: usize| {
+            format!(r#"{{"extensions": {{"KHR_lights_punctual": {{"light": {index}}}}}}}"#)
+        };
+        let nodes = format!("{}, {}, {}", light(0), light(1), light(2));
+        let lights = r#""extensions": {"KHR_lights_punctual": {"lights": [
+            {"type": "point", "intensity": 4, "range": 2},
+            {"type": "spot", "intensity": 4, "range": 2, "spot": {"outerConeAngle": 1.5}},
+            {"type": "directional", "intensity": 2}
+        ]}}"#;
+        let list = scene(&nodes, lights).light_list();
+        let irradiance = |point: [Double; 3]| -> Vec<Double> {
+            list.iter()
+                .map(|light| {
+                    light
+                        .sample_li(Point3::new(point))
+                        .map_or(0.0, |sample| sample.irradiance.r())
+                })
+                .collect()
+        };
+        // 4 (1 - (d/r)^4) / d^2
+        assert_eq!(irradiance([0.0, 0.0, -1.0]), [3.75, 3.75, 2.0]);
+        assert_eq!(irradiance([0.0, 0.0, -2.0]), [0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn material_mapping() {
+        let kind = |json: &str| classify(material(&json::parse(json).unwrap()).as_ref());
+        // the glTF defaults are a fully metallic surface
+        assert_eq!(kind("{}"), "metal");
+        assert_eq!(
+            kind(
+                r#"{"pbrMetallicRoughness": {"metallicFactor": 0.2, "baseColorFactor": [1, 0, 0, 1]}}"#
+            ),
+            "diffuse"
+        );
+        assert_eq!(
+            kind(r#"{"pbrMetallicRoughness": {"metallicFactor": 0.7, "roughnessFactor": 0}}"#),
+            "metal"
+        );
+        assert_eq!(
+            kind(r#"{"extensions": {"KHR_materials_transmission": {"transmissionFactor": 1}}}"#),
+            "glass"
+        );
+        assert_eq!(kind(r#"{"emissiveFactor": [1, 1, 0]}"#), "light");
+        assert_eq!(
+            kind(
+                r#"{"emissiveFactor": [1, 1, 1], "extensions": {"KHR_materials_emissive_strength": {"emissiveStrength": 0}}, "pbrMetallicRoughness": {"metallicFactor": 0}}"#
+            ),
+            "diffuse"
+        );
+
+        // primitives use their material, and the default one without
+        let members = r#""materials": [{"emissiveFactor": [1, 1, 1]}]"#;
+        let mesh =
+            r#""meshes": [{"primitives": [{"attributes": {"POSITION": 0}, "material": 0}]}]"#;
+        let json = document(ACCESSOR, VIEW, &format!("{}, {}, {}", NODE, mesh, members));
+        let scene = parse_gltf(json.as_bytes(), None).unwrap();
+        let ray = Ray::new(Point3::new([0.25, 0.25, 1.0]), Vector3::new(DOWN));
+        let record = scene
+            .world
+            .hit(&ray, Interval::new(0.001, Double::INFINITY))
+            .unwrap();
+        assert_eq!(classify(record.material), "light");
+        let missing = mesh.replace("\"material\": 0", "\"material\": 1");
+        let json = document(
+            ACCESSOR,
+            VIEW,
+            &format!("{}, {}, {}", NODE, missing, members),
+        );
+        assert!(parse_gltf(json.as_bytes(), None).is_err());
+    }
+
+    /// The values of accessor `index` in a document with `members` and `buffer`.
+    fn read_accessor(
+        members: &str,
+        buffer: Vec<u8>,
+        index: usize,
+    ) -> Result<Vec<Double>, LoadError> {
+        let json = json::parse(&format!("{{{}}}", members)).unwrap();
+        let document = Document {
+            json: &json,
+            buffers: vec![buffer],
+            materials: Vec::new(),
+        };
+        document.accessor(index).map(|accessor| accessor.values)
+    }
+
+    #[test]
+    fn sparse_accessors() {
+        // the triangle, then the indices 2 and 0 as u16 and two replacement vertices
+        let mut buffer = floats(&TRIANGLE);
+        buffer.extend_from_slice(&[2, 0, 0, 0]);
+        buffer.extend_from_slice(&floats(&[5.0, 6.0, 7.0, 8.0, 9.0, 10.0]));
+        let sparse = r#""sparse": {"count": 2, "indices": {"bufferView": 1, "componentType": 5123}, "values": {"bufferView": 2}}"#;
+        let views = r#""bufferViews": [
+            {"buffer": 0, "byteLength": 36},
+            {"buffer": 0, "byteOffset": 36, "byteLength": 4},
+            {"buffer": 0, "byteOffset": 40, "byteLength": 24}
+        ]"#;
+        let members = format!(
+            r#"{views}, "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", {sparse}}},
+                {{"componentType": 5126, "count": 3, "type": "VEC3", {sparse}}},
+                {{"componentType": 5126, "count": 1, "type": "VEC3", {sparse}}}
+            ]"#
+        );
+        assert_eq!(
+            read_accessor(&members, buffer.clone(), 0).unwrap(),
+            [8.0, 9.0, 10.0, 1.0, 0.0, 0.0, 5.0, 6.0, 7.0]
+        );
+        // without a buffer view the other elements are zero
+        assert_eq!(
+            read_accessor(&members, buffer.clone(), 1).unwrap(),
+            [8.0, 9.0, 10.0, 0.0, 0.0, 0.0, 5.0, 6.0, 7.0]
+        );
+        // index 2 is past the single element
+        assert!(read_accessor(&members, buffer, 2).is_err());
+    }
+
+    fn assert_invalid(accessor: &str, view: &str) {
+        match load(accessor, view) {
+            Err(LoadError::Invalid { .. }) => {}
+            Err(err) => panic!("expected an invalid file, got {}", err),
+            Ok(_) => panic!("expected an invalid file"),
+        }
+    }
+
+    #[test]
+    fn malformed_accessors_are_errors() {
+        let huge = "18446744073709551615";
+        for accessor in [
+            // elements past the end of the view
+            r#"{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}"#,
+            r#"{"bufferView": 0, "byteOffset": 4, "componentType": 5126, "count": 3, "type": "VEC3"}"#,
+            // sizes that overflow
+            &format!(
+                r#"{{"bufferView": 0, "componentType": 5126, "count": {}, "type": "VEC3"}}"#,
+                huge
+            ),
+            &format!(
+                r#"{{"bufferView": 0, "byteOffset": {}, "componentType": 5126, "count": 3, "type": "VEC3"}}"#,
+                huge
+            ),
+            // zeros the file cannot justify
+            r#"{"componentType": 5126, "count": 1000000000, "type": "MAT4"}"#,
+            &format!(
+                r#"{{"componentType": 5126, "count": {}, "type": "VEC3"}}"#,
+                huge
+            ),
+        ] {
+            assert_invalid(accessor, VIEW);
+        }
+        for view in [
+            r#"{"buffer": 0, "byteLength": 36, "byteStride": 4}"#,
+            r#"{"buffer": 0, "byteLength": 36, "byteStride": 1000000000000}"#,
+            &format!(
+                r#"{{"buffer": 0, "byteLength": 36, "byteStride": {}}}"#,
+                huge
+            ),
+            &format!(r#"{{"buffer": 0, "byteLength": {}}}"#, huge),
+            &format!(
+                r#"{{"buffer": 0, "byteOffset": {}, "byteLength": 36}}"#,
+                huge
+            ),
+            r#"{"buffer": 0, "byteLength": 48}"#,
+        ] {
+            assert_invalid(ACCESSOR, view);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    aabb::Aabb,
+    color::RGB,
+    hittable::{Hittable, HittableBox, bvh::BvhNode, triangle},
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    vec3::{Point3, Vector3},
+};
+
+use super::HitRecord;
+
+/// Vertex buffers and faces shared by all triangles of a mesh.
+#[derive(Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<Point3>,
+    pub normals: Vec<Vector3>,
+    pub uvs: Vec<[Double; 2]>,
+    /// Per vertex colors, indexed like `positions`, or empty
+    pub colors: Vec<RGB>,
+    pub faces: Vec<Face>,
+    /// Materials referenced by [`Face::material`]
+    pub materials: Vec<SharedMaterial>,
+    /// Names of the groups referenced by [`Face::group`]
+    pub groups: Vec<String>,
+}
+
+/// A triangle of the mesh, as indices into the [`MeshBuffers`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Face {
+    pub positions: [u32; 3],
+    pub normals: Option<[u32; 3]>,
+    pub uvs: Option<[u32; 3]>,
+    pub material: u32,
+    pub group: u32,
+}
+
+impl MeshBuffers {
+    /// Use `material` for every face.
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.materials = vec![material];
+        self.faces.iter_mut().for_each(|face| face.material = 0);
+        self
+    }
+    /// Check that every index of the faces points into its buffer, and that there is
+    /// one color per position if there are any.
+    fn validate(&self) -> Result<(), LoadError> {
+        if !self.colors.is_empty() && self.colors.len() != self.positions.len() {
+            return Err(LoadError::invalid(format!(
+                "{} colors for {} positions",
+                self.colors.len(),
+                self.positions.len()
+            )));
+        }
+        let check = |face: usize, kind: &str, indices: &[u32], len: usize| {
+            if let Some(i) = indices.iter().find(|&&i| i as usize >= len) {
+                return Err(LoadError::invalid(format!(
+                    "face {}: {} index {} out of range, there are {}",
+                    face, kind, i, len
+                )));
+            }
+            Ok(())
+        };
+        for (index, face) in self.faces.iter().enumerate() {
+            check(index, "position", &face.positions, self.positions.len())?;
+            if let Some(normals) = &face.normals {
+                check(index, "normal", normals, self.normals.len())?;
+            }
+            if let Some(uvs) = &face.uvs {
+                check(index, "uv", uvs, self.uvs.len())?;
+            }
+            check(index, "material", &[face.material], self.materials.len())?;
+        }
+        Ok(())
+    }
+    fn vertices(&self, face: &Face) -> [Point3; 3] {
+        face.positions.map(|i| self.positions[i as usize])
+    }
+}
+
+/// Triangle mesh, accelerated by its own [`BvhNode`].
+pub struct TriangleMesh {
+    buffers: Arc<MeshBuffers>,
+    bvh: BvhNode,
+}
+
+impl TriangleMesh {
+    /// Fails if a face refers to a position, normal, uv or material which does not exist,
+    /// or the colors do not match the positions.
+    pub fn new(mut buffers: MeshBuffers) -> Result<Self, LoadError> {
+        if buffers.materials.is_empty() {
+            buffers.materials.push(default_material());
+        }
+        buffers.validate()?;
+        let buffers = Arc::new(buffers);
+        let triangles: Vec<HittableBox> = (0..buffers.faces.len())
+            .map(|face| {
+                MeshTriangle {
+                    buffers: buffers.clone(),
+                    face: face as u32,
+                }
+                .into()
+            })
+            .collect();
+        Ok(Self {
+            bvh: BvhNode::new(triangles),
+            buffers,
+        })
+    }
+    pub fn buffers(&self) -> &MeshBuffers {
+        &self.buffers
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        self.bvh.hit(ray, ray_t_range)
+    }
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+}
+
+/// One face of a [`TriangleMesh`].
+struct MeshTriangle {
+    buffers: Arc<MeshBuffers>,
+    face: u32,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let buffers = &*self.buffers;
+        let face = &buffers.faces[self.face as usize];
+        let vertices = buffers.vertices(face);
+        let (t, barycentric) = triangle::intersect(ray, &vertices, ray_t_range)?;
+        let [a, b, c] = vertices;
+        let outward_normal = (b - a).cross(c - a).unit_vector();
+        let material = buffers.materials[face.material as usize].as_ref();
+        let record = HitRecord::new(ray, t, ray.at(t), outward_normal, material);
+        let normals = face.normals.map(|n| n.map(|i| buffers.normals[i as usize]));
+        let uvs = face.uvs.map(|n| n.map(|i| buffers.uvs[i as usize]));
+        let record = triangle::shade(record, barycentric, normals.as_ref(), uvs.as_ref());
+        if buffers.colors.is_empty() {
+            return Some(record);
+        }
+        let [c0, c1, c2] = face.positions.map(|i| buffers.colors[i as usize]);
+        let [w, u, v] = barycentric;
+        Some(record.with_color(c0 * w + c1 * u + c2 * v))
+    }
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self
+            .buffers
+            .vertices(&self.buffers.faces[self.face as usize]);
+        Aabb::enclosing(Aabb::from_points(a, b), Aabb::from_points(c, c))
+    }
+}
+
+/// Error of the mesh and scene loaders.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// Malformed input, `line` counts from 1 (or is the byte offset for binary formats)
+    Parse {
+        path: Option<PathBuf>,
+        line: usize,
+        message: String,
+    },
+    /// Well formed input describing something inconsistent, like an index out of range
+    Invalid {
+        path: Option<PathBuf>,
+        message: String,
+    },
+}
+
+impl LoadError {
+    pub(crate) fn parse(line: usize, message: impl Into<String>) -> Self {
+        LoadError::Parse {
+            path: None,
+            line,
+            message: message.into(),
+        }
+    }
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        LoadError::Invalid {
+            path: None,
+            message: message.into(),
+        }
+    }
+    /// Attach the file the error happened in, unless it already names one.
+    pub(crate) fn in_file(self, file: impl Into<PathBuf>) -> Self {
+        match self {
+            LoadError::Parse {
+                path: None,
+                line,
+                message,
+            } => LoadError::Parse {
+                path: Some(file.into()),
+                line,
+                message,
+            },
+            LoadError::Invalid {
+                path: None,
+                message,
+            } => LoadError::Invalid {
+                path: Some(file.into()),
+                message,
+            },
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "{}", err),
+            LoadError::Parse {
+                path: Some(path),
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            LoadError::Parse {
+                path: None,
+                line,
+                message,
+            } => write!(f, "line {}: {}", line, message),
+            LoadError::Invalid {
+                path: Some(path),
+                message,
+            } => write!(f, "{}: {}", path.display(), message),
+            LoadError::Invalid {
+                path: None,
+                message,
+            } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            LoadError::Parse { .. } | LoadError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(err: io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A single triangle with normals, uvs and colors.
+    fn triangle() -> MeshBuffers {
+        MeshBuffers {
+            positions: vec![
+                Point3::default(),
+                Point3::default().with_x(1.0),
+                Point3::default().with_y(1.0),
+            ],
+            normals: vec![Vector3::default().with_z(1.0)],
+            uvs: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            colors: vec![RGB::new([1.0; 3]); 3],
+            faces: vec![Face {
+                positions: [0, 1, 2],
+                normals: Some([0; 3]),
+                uvs: Some([0, 1, 2]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn error(buffers: MeshBuffers) -> String {
+        match TriangleMesh::new(buffers) {
+            Err(err @ LoadError::Invalid { .. }) => err.to_string(),
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("invalid mesh was accepted"),
+        }
+    }
+
+    #[test]
+    fn valid_mesh_is_accepted() {
+        let mesh = TriangleMesh::new(triangle()).unwrap();
+        // the default material is filled in
+        assert_eq!(mesh.buffers().materials.len(), 1);
+        let ray = Ray::new(
+            Point3::new([0.2, 0.2, 1.0]),
+            Vector3::default().with_z(-1.0),
+        );
+        let record = mesh.hit(&ray, Interval::new(0.001, f64::INFINITY)).unwrap();
+        let color = record.color.unwrap();
+        assert!(color.0.iter().all(|n| (n - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn indices_out_of_range_are_errors() {
+        let mut buffers = triangle();
+        buffers.faces[0].positions[2] = 3;
+        assert_eq!(
+            error(buffers),
+            "face 0: position index 3 out of range, there are 3"
+        );
+        let mut buffers = triangle();
+        buffers.faces[0].normals = Some([0, 0, 1]);
+        assert!(error(buffers).contains("normal index 1"));
+        let mut buffers = triangle();
+        buffers.faces[0].uvs = Some([0, 7, 2]);
+        assert!(error(buffers).contains("uv index 7"));
+        let mut buffers = triangle();
+        buffers.faces[0].material = 1;
+        assert!(error(buffers).contains("material index 1"));
+        // indices are only checked where the face has them
+        let mut buffers = triangle();
+        buffers.normals.clear();
+        buffers.faces[0].normals = None;
+        assert!(TriangleMesh::new(buffers).is_ok());
+    }
+
+    #[test]
+    fn colors_must_match_the_positions() {
+        let mut buffers = triangle();
+        buffers.colors.pop();
+        assert_eq!(error(buffers), "2 colors for 3 positions");
+        let mut buffers = triangle();
+        buffers.colors.clear();
+        assert!(TriangleMesh::new(buffers).is_ok());
+    }
+}
@@ -1,13 +1,44 @@
 use crate::{
     Double,
+    aabb::Aabb,
+    color::RGB,
     interval::Interval,
+    material::Material,
     ray::Ray,
+    sampler::Sampler,
     vec3::{Point3, Vector3},
 };
+pub mod axis_box;
+pub mod bvh;
+pub mod disk;
+pub mod gltf;
+pub mod mesh;
+pub mod obj;
+pub mod plane;
+pub mod ply;
+pub mod quad;
 pub mod sphere;
+pub mod stl;
+pub mod triangle;
+/// Ignore hits closer than this to the ray origin, a scattered ray would
+/// otherwise hit the surface it starts on again because of rounding errors.
+pub const SHADOW_ACNE_EPSILON: Double = 0.001;
+
 pub type HittableBox = Box<dyn Hittable + 'static>;
-pub trait Hittable {
-    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord>;
+pub trait Hittable: Send + Sync {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>>;
+    /// Box enclosing the whole object, used to build a [`bvh::BvhNode`].
+    fn bounding_box(&self) -> Aabb;
+    /// Density, by solid angle seen from `origin`, of [`Hittable::random`] choosing `direction`.
+    ///
+    /// Zero for objects which cannot be sampled as lights.
+    fn pdf_value(&self, _origin: Point3, _direction: Vector3) -> Double {
+        0.0
+    }
+    /// Random direction from `origin` towards the object, not necessarily of unit length.
+    fn random(&self, _origin: Point3, _sampler: &mut dyn Sampler) -> Vector3 {
+        Vector3::new([1.0, 0.0, 0.0])
+    }
 }
 
 impl<H: Hittable + 'static> From<H> for HittableBox {
@@ -16,7 +47,7 @@ impl<H: Hittable + 'static> From<H> for HittableBox {
     }
 }
 impl Hittable for HittableList {
-    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
         let mut record = None;
         let mut range = ray_t_range;
         for obj in &self.objects {
@@ -27,6 +58,31 @@ impl Hittable for HittableList {
         }
         record
     }
+    fn bounding_box(&self) -> Aabb {
+        self.objects
+            .iter()
+            .fold(Aabb::EMPTY, |acc, obj| Aabb::enclosing(acc, obj.bounding_box()))
+    }
+    /// Every object is picked with the same probability.
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> Double {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: Double = self
+            .objects
+            .iter()
+            .map(|obj| obj.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as Double
+    }
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let count = self.objects.len();
+        if count == 0 {
+            return Vector3::new([1.0, 0.0, 0.0]);
+        }
+        let index = ((sampler.get_1d() * count as Double) as usize).min(count - 1);
+        self.objects[index].random(origin, sampler)
+    }
 }
 
 #[derive(Default)]
@@ -53,17 +109,30 @@ impl HittableList {
     }
 }
 
-#[derive(Default)]
-pub struct HitRecord {
+pub struct HitRecord<'a> {
     pub point: Point3,
     pub ray_t: Double,
     pub normal: Vector3,
     pub normal_direction: NormalDirection,
+    /// Material of the object which was hit
+    pub material: &'a dyn Material,
+    /// Surface texture coordinates of the hit point
+    pub uv: [Double; 2],
+    /// Barycentric coordinates of the hit point, for triangles
+    pub barycentric: [Double; 3],
+    /// Interpolated vertex color, for meshes which have one
+    pub color: Option<RGB>,
 }
 
-impl HitRecord {
+impl<'a> HitRecord<'a> {
     /// NOTE: the parameter `outward_normal` is assumed to have unit length.
-    pub fn new(ray: &Ray, ray_t: Double, point: Point3, outward_normal: Vector3) -> Self {
+    pub fn new(
+        ray: &Ray,
+        ray_t: Double,
+        point: Point3,
+        outward_normal: Vector3,
+        material: &'a dyn Material,
+    ) -> Self {
         let (normal, normal_direction) = if ray.direction.dot(outward_normal) < 0.0 {
             //ray is outside the sphere
             (outward_normal, NormalDirection::Outward)
@@ -76,8 +145,34 @@ impl HitRecord {
             normal,
             ray_t,
             normal_direction,
+            material,
+            uv: [0.0; 2],
+            barycentric: [0.0; 3],
+            color: None,
         }
     }
+    pub fn with_uv(mut self, u: Double, v: Double) -> Self {
+        self.uv = [u, v];
+        self
+    }
+    pub fn with_barycentric(mut self, barycentric: [Double; 3]) -> Self {
+        self.barycentric = barycentric;
+        self
+    }
+    pub fn with_color(mut self, color: RGB) -> Self {
+        self.color = Some(color);
+        self
+    }
+    /// Replace the normal used for shading, e.g. by one interpolated over a mesh.
+    /// The side of the surface stays the one decided by the geometric normal.
+    pub fn with_shading_normal(mut self, outward_normal: Vector3) -> Self {
+        self.normal = if self.normal_direction.is_outward() {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        self
+    }
 }
 #[derive(Default)]
 pub enum NormalDirection {
@@ -95,3 +190,49 @@ impl NormalDirection {
         matches!(self, Self::Outward)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::sampler::independent::IndependentSampler;
+    use std::f64::consts::PI;
+
+    /// Check the light sampling of `object` as seen from `origin`: `pdf_value` integrates
+    /// to 1 over the directions which hit it, `random` only picks such directions,
+    /// and both agree on the solid angle the object covers.
+    pub(crate) fn assert_light_sampling(object: &dyn Hittable, origin: Point3) {
+        let mut sampler = IndependentSampler::new(7);
+        let count = 200_000;
+        let range = Interval::new(0.001, f64::INFINITY);
+        let (mut integral, mut hits) = (0.0, 0);
+        for _ in 0..count {
+            let direction = Vector3::random_unit(&mut sampler);
+            let pdf = object.pdf_value(origin, direction);
+            let hit = object.hit(&Ray::new(origin, direction), range).is_some();
+            assert_eq!(pdf > 0.0, hit, "pdf {pdf} towards {direction:?}");
+            integral += pdf;
+            hits += hit as usize;
+        }
+        // uniform directions have the density 1 / 4π
+        let integral = integral * 4.0 * PI / count as Double;
+        // the estimates are only as good as the number of directions which hit
+        let tolerance = 4.0 / (hits as Double).sqrt();
+        assert!(
+            (integral - 1.0).abs() < tolerance,
+            "pdf integrates to {integral}"
+        );
+        let solid_angle = 4.0 * PI * hits as Double / count as Double;
+        let mut estimate = 0.0;
+        for _ in 0..count {
+            let direction = object.random(origin, &mut sampler);
+            let pdf = object.pdf_value(origin, direction);
+            assert!(pdf > 0.0, "random direction {direction:?} misses");
+            estimate += 1.0 / pdf;
+        }
+        let estimate = estimate / count as Double;
+        assert!(
+            (estimate - solid_angle).abs() < tolerance * solid_angle,
+            "solid angle {estimate} instead of {solid_angle}"
+        );
+    }
+}
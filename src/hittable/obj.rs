@@ -0,0 +1,465 @@
+//! Wavefront OBJ and MTL reader.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    color::RGB,
+    material::{
+        SharedMaterial, default_material, dielectric::Dielectric, diffuse_light::DiffuseLight,
+        lambertian::Lambertian, metal::Metal,
+    },
+    vec3::{Point3, Vector3},
+};
+
+use super::mesh::{Face, LoadError, MeshBuffers, TriangleMesh};
+
+/// Read the `.obj` file at `path`, along with the `.mtl` libraries it references.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    let buffers = parse_obj(&source, path.parent()).map_err(|err| err.in_file(path))?;
+    TriangleMesh::new(buffers).map_err(|err| err.in_file(path))
+}
+
+/// Parse the OBJ `source`, `mtllib` files are looked up relative to `base_dir`.
+///
+/// Supports `v`, `vn`, `vt`, `f` (polygons are triangulated as a fan, indices may
+/// be negative), `g`/`o` groups, `mtllib` and `usemtl`. Other statements are ignored.
+/// Material libraries which cannot be read are skipped, their faces get the default material.
+pub fn parse_obj(source: &str, base_dir: Option<&Path>) -> Result<MeshBuffers, LoadError> {
+    let mut buffers = MeshBuffers {
+        groups: vec!["default".to_string()],
+        ..Default::default()
+    };
+    let mut library: HashMap<String, SharedMaterial> = HashMap::new();
+    // materials already copied into the buffers, by name
+    let mut material_indices: HashMap<String, u32> = HashMap::new();
+    let mut current_material = None;
+    let mut current_group = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let rest: Vec<&str> = tokens.collect();
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats(&rest, 3, line_number)?;
+                buffers.positions.push(Point3::new([x, y, z]));
+            }
+            "vn" => {
+                let [x, y, z] = parse_floats(&rest, 3, line_number)?;
+                let normal = Vector3::new([x, y, z]);
+                // a zero normal stays zero, the faces using it fall back to the face normal
+                buffers.normals.push(if normal.near_zero() {
+                    Vector3::default()
+                } else {
+                    normal.unit_vector()
+                });
+            }
+            "vt" => {
+                // v and w are optional
+                let [u, v, _] = parse_floats(&rest, 1, line_number)?;
+                buffers.uvs.push([u, v]);
+            }
+            "f" => {
+                if rest.len() < 3 {
+                    return Err(LoadError::parse(
+                        line_number,
+                        "face needs at least 3 vertices",
+                    ));
+                }
+                let vertices = rest
+                    .iter()
+                    .map(|token| parse_face_vertex(token, &buffers, line_number))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let material = match current_material {
+                    Some(material) => material,
+                    None => {
+                        let material = default_material_index(&mut buffers, &mut material_indices);
+                        current_material = Some(material);
+                        material
+                    }
+                };
+                // triangulate as a fan around the first vertex
+                for i in 1..vertices.len() - 1 {
+                    let corners = [vertices[0], vertices[i], vertices[i + 1]];
+                    buffers.faces.push(Face {
+                        positions: corners.map(|c| c.0),
+                        uvs: all_some(corners.map(|c| c.1)),
+                        normals: all_some(corners.map(|c| c.2)).filter(|normals| {
+                            normals
+                                .iter()
+                                .all(|&n| !buffers.normals[n as usize].near_zero())
+                        }),
+                        material,
+                        group: current_group,
+                    });
+                }
+            }
+            "g" | "o" => {
+                let name = if rest.is_empty() {
+                    "default".to_string()
+                } else {
+                    rest.join(" ")
+                };
+                current_group = match buffers.groups.iter().position(|group| *group == name) {
+                    Some(group) => group as u32,
+                    None => {
+                        buffers.groups.push(name);
+                        buffers.groups.len() as u32 - 1
+                    }
+                };
+            }
+            "mtllib" => {
+                for file_name in &rest {
+                    let mtl_path = match base_dir {
+                        Some(dir) => dir.join(file_name),
+                        None => file_name.into(),
+                    };
+                    // a missing library leaves its materials unknown, like a bad usemtl name
+                    let Ok(mtl_source) = fs::read_to_string(&mtl_path) else {
+                        continue;
+                    };
+                    let materials = parse_mtl(&mtl_source).map_err(|err| err.in_file(&mtl_path))?;
+                    library.extend(materials);
+                }
+            }
+            "usemtl" => {
+                let name = rest.join(" ");
+                let material = match material_indices.get(&name) {
+                    Some(&material) => material,
+                    // unknown materials render with the default one
+                    None => match library.get(&name) {
+                        Some(material) => {
+                            buffers.materials.push(material.clone());
+                            let material = buffers.materials.len() as u32 - 1;
+                            material_indices.insert(name, material);
+                            material
+                        }
+                        None => default_material_index(&mut buffers, &mut material_indices),
+                    },
+                };
+                current_material = Some(material);
+            }
+            _ => {}
+        }
+    }
+    Ok(buffers)
+}
+
+/// Parse a `.mtl` material library into materials by name.
+///
+/// `Kd`, `Ks`, `Ke`, `Ns`, `Ni`, `d`/`Tr` and `illum` decide between a diffuse, metal, glass or light material.
+pub fn parse_mtl(source: &str) -> Result<Vec<(String, SharedMaterial)>, LoadError> {
+    let mut materials = Vec::new();
+    let mut current: Option<MtlEntry> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let rest: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            if let Some(entry) = current.take() {
+                materials.push(entry.into_material());
+            }
+            current = Some(MtlEntry::new(rest.join(" ")));
+            continue;
+        }
+        let Some(entry) = current.as_mut() else {
+            // statements before the first newmtl have nothing to apply to
+            continue;
+        };
+        match keyword {
+            "Kd" => entry.diffuse = parse_floats(&rest, 3, line_number)?,
+            "Ks" => entry.specular = parse_floats(&rest, 3, line_number)?,
+            "Ke" => entry.emission = parse_floats(&rest, 3, line_number)?,
+            "Ns" => entry.shininess = parse_floats::<1>(&rest, 1, line_number)?[0],
+            "Ni" => entry.refraction_index = parse_floats::<1>(&rest, 1, line_number)?[0],
+            "d" => entry.dissolve = parse_floats::<1>(&rest, 1, line_number)?[0],
+            "Tr" => entry.dissolve = 1.0 - parse_floats::<1>(&rest, 1, line_number)?[0],
+            "illum" => {
+                let illum = rest
+                    .first()
+                    .and_then(|token| token.parse().ok())
+                    .ok_or_else(|| {
+                        LoadError::parse(line_number, "expected an illumination model")
+                    })?;
+                entry.illum = Some(illum);
+            }
+            _ => {}
+        }
+    }
+    if let Some(entry) = current.take() {
+        materials.push(entry.into_material());
+    }
+    Ok(materials)
+}
+
+/// One `newmtl` block of an MTL file.
+struct MtlEntry {
+    name: String,
+    diffuse: [Double; 3],
+    specular: [Double; 3],
+    emission: [Double; 3],
+    shininess: Double,
+    refraction_index: Double,
+    dissolve: Double,
+    illum: Option<u32>,
+}
+
+impl MtlEntry {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            diffuse: [0.8; 3],
+            specular: [0.0; 3],
+            emission: [0.0; 3],
+            shininess: 0.0,
+            refraction_index: 1.0,
+            dissolve: 1.0,
+            illum: None,
+        }
+    }
+    fn into_material(self) -> (String, SharedMaterial) {
+        let max = |c: [Double; 3]| c[0].max(c[1]).max(c[2]);
+        let material: SharedMaterial = match self.illum {
+            // emitters do not reflect in this renderer
+            _ if max(self.emission) > 0.0 => Arc::new(DiffuseLight::new(RGB::new(self.emission))),
+            // transparent, refractive illumination models
+            Some(4 | 6 | 7) => Arc::new(Dielectric::new(self.refraction_index)),
+            // a slightly dissolved surface stays opaque, only a mostly clear refractive one is glass
+            _ if self.dissolve <= 0.5 && self.refraction_index > 1.0 => {
+                Arc::new(Dielectric::new(self.refraction_index))
+            }
+            // reflective models, or a surface which is only specular
+            Some(3 | 5) => Arc::new(self.metal()),
+            _ if max(self.diffuse) == 0.0 && max(self.specular) > 0.0 => Arc::new(self.metal()),
+            _ => Arc::new(Lambertian::new(RGB::new(self.diffuse))),
+        };
+        (self.name, material)
+    }
+    fn metal(&self) -> Metal {
+        // a higher specular exponent gives a sharper highlight, so less fuzz
+        let fuzz = (2.0 / (self.shininess + 2.0)).sqrt();
+        Metal::new(RGB::new(self.specular), fuzz)
+    }
+}
+
+/// Parse at least `required` and at most `N` numbers, missing ones are zero.
+fn parse_floats<const N: usize>(
+    tokens: &[&str],
+    required: usize,
+    line: usize,
+) -> Result<[Double; N], LoadError> {
+    if tokens.len() < required {
+        return Err(LoadError::parse(
+            line,
+            format!("expected {} numbers, found {}", required, tokens.len()),
+        ));
+    }
+    let mut values = [0.0; N];
+    for (value, token) in values.iter_mut().zip(tokens) {
+        *value = token
+            .parse()
+            .map_err(|_| LoadError::parse(line, format!("invalid number `{}`", token)))?;
+    }
+    Ok(values)
+}
+
+/// Position, texture coordinate and normal indices of one `f` vertex (`v`, `v/vt`, `v//vn` or `v/vt/vn`).
+fn parse_face_vertex(
+    token: &str,
+    buffers: &MeshBuffers,
+    line: usize,
+) -> Result<(u32, Option<u32>, Option<u32>), LoadError> {
+    let mut parts = token.split('/');
+    let position = parse_index(parts.next(), buffers.positions.len(), "vertex", line)?
+        .ok_or_else(|| LoadError::parse(line, format!("missing vertex index in `{}`", token)))?;
+    let uv = parse_index(parts.next(), buffers.uvs.len(), "texture coordinate", line)?;
+    let normal = parse_index(parts.next(), buffers.normals.len(), "normal", line)?;
+    Ok((position, uv, normal))
+}
+
+/// Resolve a 1-based index, negative indices count back from the last element read so far.
+fn parse_index(
+    token: Option<&str>,
+    count: usize,
+    what: &str,
+    line: usize,
+) -> Result<Option<u32>, LoadError> {
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        return Ok(None);
+    };
+    let index: i64 = token
+        .parse()
+        .map_err(|_| LoadError::parse(line, format!("invalid {} index `{}`", what, token)))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..0 => count as i64 + index,
+        0 => -1,
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(LoadError::parse(
+            line,
+            format!("{} index {} out of range", what, index),
+        ));
+    }
+    Ok(Some(resolved as u32))
+}
+
+fn all_some(indices: [Option<u32>; 3]) -> Option<[u32; 3]> {
+    match indices {
+        [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+        _ => None,
+    }
+}
+
+fn default_material_index(
+    buffers: &mut MeshBuffers,
+    material_indices: &mut HashMap<String, u32>,
+) -> u32 {
+    // the empty name cannot be declared by newmtl
+    *material_indices.entry(String::new()).or_insert_with(|| {
+        buffers.materials.push(default_material());
+        buffers.materials.len() as u32 - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::tests::classify;
+
+    fn parse(source: &str) -> MeshBuffers {
+        parse_obj(source, None).unwrap()
+    }
+
+    fn parse_error_line(source: &str) -> usize {
+        match parse_obj(source, None) {
+            Err(LoadError::Parse { line, .. }) => line,
+            Err(err) => panic!("unexpected error {err}"),
+            Ok(_) => panic!("parsed"),
+        }
+    }
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn polygons_are_triangulated_as_a_fan() {
+        let buffers = parse(&format!("{SQUARE}v 0.5 1.5 0\nf 1 2 3 5 4\n"));
+        let faces: Vec<_> = buffers.faces.iter().map(|face| face.positions).collect();
+        assert_eq!(faces, [[0, 1, 2], [0, 2, 4], [0, 4, 3]]);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let buffers = parse(&format!("{SQUARE}f -4 -3 -2\nv 2 2 0\nf -1 -2 -3\n"));
+        let faces: Vec<_> = buffers.faces.iter().map(|face| face.positions).collect();
+        assert_eq!(faces, [[0, 1, 2], [4, 3, 2]]);
+    }
+
+    #[test]
+    fn vertices_with_normals_and_uvs() {
+        let source = format!(
+            "{SQUARE}vn 0 0 2\nvt 0 0\nvt 1 0\nvt 1 1\n\
+             f 1//1 2//1 3//1\nf 1/1 2/2 3/3\nf 1/1/1 2/2/1 3/3/1\nf 1 2 3\n"
+        );
+        let buffers = parse(&source);
+        let faces = &buffers.faces;
+        assert_eq!(faces[0].normals, Some([0; 3]));
+        assert_eq!(faces[0].uvs, None);
+        assert_eq!(faces[1].normals, None);
+        assert_eq!(faces[1].uvs, Some([0, 1, 2]));
+        assert_eq!(faces[2].normals, Some([0; 3]));
+        assert_eq!(faces[2].uvs, Some([0, 1, 2]));
+        assert_eq!((faces[3].normals, faces[3].uvs), (None, None));
+        // normals are normalized
+        assert_eq!(buffers.normals[0].0, [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn zero_normals_are_dropped() {
+        let buffers = parse(&format!("{SQUARE}vn 0 0 0\nvn 0 0 1\nf 1//1 2//2 3//2\n"));
+        assert!(
+            buffers
+                .normals
+                .iter()
+                .all(|n| n.0.iter().all(|c| c.is_finite()))
+        );
+        assert_eq!(buffers.faces[0].normals, None);
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        assert_eq!(parse_error_line(&format!("{SQUARE}\nf 1 2 5\n")), 6);
+        assert_eq!(parse_error_line(&format!("{SQUARE}f 1 2 -5\n")), 5);
+        assert_eq!(parse_error_line(&format!("{SQUARE}f 1 2 0\n")), 5);
+        assert_eq!(parse_error_line(&format!("{SQUARE}f 1//1 2 3\n")), 5);
+        assert_eq!(parse_error_line(&format!("{SQUARE}f 1 2\n")), 5);
+        assert_eq!(parse_error_line("v 0 0\n"), 1);
+        assert_eq!(parse_error_line("# comment\nv 0 x 0\n"), 2);
+    }
+
+    #[test]
+    fn mtl_statements_pick_the_material() {
+        let source = "\
+            newmtl matte\nKd 0.5 0.2 0.1\n\
+            newmtl lamp\nKe 4 4 4\n\
+            newmtl mirror\nKd 0 0 0\nKs 0.9 0.9 0.9\nNs 1000\n\
+            newmtl polished\nKd 0.5 0.5 0.5\nKs 1 1 1\nillum 3\n\
+            newmtl glass\nNi 1.5\nillum 7\n\
+            newmtl clear\nNi 1.5\nd 0.1\n\
+            newmtl plastic\nKd 0.8 0.1 0.1\nNi 1.5\nd 0.99\n\
+            newmtl faded\nKd 0.8 0.1 0.1\nTr 0.05\n";
+        let materials = parse_mtl(source).unwrap();
+        let kinds: Vec<_> = materials
+            .iter()
+            .map(|(name, material)| (name.as_str(), classify(material.as_ref())))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("matte", "diffuse"),
+                ("lamp", "light"),
+                ("mirror", "metal"),
+                ("polished", "metal"),
+                ("glass", "glass"),
+                ("clear", "glass"),
+                ("plastic", "diffuse"),
+                ("faded", "diffuse"),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_material_library_is_read() {
+        let dir = std::env::temp_dir().join(format!("obj-mtllib-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.mtl"), "newmtl lamp\nKe 1 1 1\n").unwrap();
+        fs::write(dir.join("b.mtl"), "newmtl glass\nNi 1.5\nillum 4\n").unwrap();
+        let source = format!(
+            "mtllib a.mtl missing.mtl b.mtl\n{SQUARE}\
+             usemtl lamp\nf 1 2 3\nusemtl glass\nf 1 3 4\nusemtl unknown\nf 2 3 4\n"
+        );
+        let buffers = parse_obj(&source, Some(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+        let buffers = buffers.unwrap();
+        let kinds: Vec<_> = buffers
+            .faces
+            .iter()
+            .map(|face| classify(buffers.materials[face.material as usize].as_ref()))
+            .collect();
+        assert_eq!(kinds, ["light", "glass", "diffuse"]);
+    }
+}
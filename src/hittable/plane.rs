@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    vec3::{Point3, Vector3},
+};
+
+use super::HitRecord;
+
+/// Infinite plane through `point`, facing `normal`.
+///
+/// Its bounding box is unbounded, so keep it out of a [`BvhNode`](super::bvh::BvhNode).
+pub struct Plane {
+    pub point: Point3,
+    /// Unit normal on the front side
+    pub normal: Vector3,
+    pub material: SharedMaterial,
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+        let t = self.normal.dot(self.point - ray.origin) / denominator;
+        if !ray_t_range.surrounds(t) {
+            return None;
+        }
+        let point = ray.at(t);
+        // texture coordinates in world units along the tangents, a texture repeats every unit
+        let (tangent, bitangent) = tangent_frame(self.normal);
+        let planar = point - self.point;
+        let record = HitRecord::new(ray, t, point, self.normal, self.material.as_ref())
+            .with_uv(planar.dot(tangent), planar.dot(bitangent));
+        Some(record)
+    }
+    fn bounding_box(&self) -> Aabb {
+        // a plane facing an axis is thin along it, any other plane fills the space
+        let axis = (0..3).find(|&axis| self.normal[axis].abs() == 1.0);
+        let [x, y, z] = [0, 1, 2].map(|n| match axis {
+            Some(axis) if axis == n => Interval::new(self.point[n], self.point[n]),
+            _ => Interval::UNIVERSE,
+        });
+        Aabb::new(x, y, z)
+    }
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vector3) -> Self {
+        Self {
+            point,
+            normal: normal.unit_vector(),
+            material: default_material(),
+        }
+    }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.material = material;
+        self
+    }
+}
+
+/// Two unit vectors which form an orthonormal basis with the unit vector `normal`.
+///
+/// Duff et al., "Building an Orthonormal Basis, Revisited", without a branch on the axis.
+pub(crate) fn tangent_frame(normal: Vector3) -> (Vector3, Vector3) {
+    let [x, y, z] = normal.0;
+    let sign: Double = 1.0_f64.copysign(z);
+    let a = -1.0 / (sign + z);
+    let b = x * y * a;
+    let tangent = Vector3::new([1.0 + sign * x * x * a, sign * b, -sign * x]);
+    let bitangent = Vector3::new([b, sign + y * y * a, -y]);
+    (tangent, bitangent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    #[test]
+    fn hits_either_side() {
+        let plane = Plane::new(Point3::default().with_y(1.0), Vector3::new([0.0, 2.0, 0.0]));
+        let range = Interval::new(0.001, f64::INFINITY);
+        let down = Ray::new(
+            Point3::new([3.0, 4.0, -2.0]),
+            Vector3::new([0.0, -1.0, 0.0]),
+        );
+        let front = plane.hit(&down, range).unwrap();
+        assert_eq!(front.ray_t, 3.0);
+        assert!(front.normal_direction.is_outward());
+        assert_eq!(front.normal, Vector3::new([0.0, 1.0, 0.0]));
+        let up = Ray::new(
+            Point3::new([3.0, -4.0, -2.0]),
+            Vector3::new([0.0, 1.0, 0.0]),
+        );
+        let back = plane.hit(&up, range).unwrap();
+        assert!(!back.normal_direction.is_outward());
+        assert_eq!(back.normal, Vector3::new([0.0, -1.0, 0.0]));
+        assert!(plane.hit(&down, Interval::new(0.001, 2.9)).is_none());
+        let parallel = Ray::new(Point3::default(), Vector3::new([1.0, 0.0, 1.0]));
+        assert!(plane.hit(&parallel, range).is_none());
+    }
+
+    #[test]
+    fn uv_follows_the_tangents() {
+        let normal = Vector3::new([1.0, 2.0, -2.0]);
+        let plane = Plane::new(Point3::new([1.0, 1.0, 1.0]), normal);
+        let (tangent, bitangent) = tangent_frame(plane.normal);
+        let target = plane.point + tangent * 0.75 + bitangent * -2.5;
+        let origin = target + normal;
+        let record = plane
+            .hit(
+                &Ray::new(origin, target - origin),
+                Interval::new(0.001, f64::INFINITY),
+            )
+            .unwrap();
+        assert!((record.uv[0] - 0.75).abs() < 1e-12);
+        assert!((record.uv[1] + 2.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bounding_box_is_thin_only_facing_an_axis() {
+        let facing = Plane::new(
+            Point3::default().with_z(2.0),
+            Vector3::default().with_z(-1.0),
+        );
+        let bbox = facing.bounding_box();
+        assert!(bbox.z.contains(2.0) && bbox.z.size() < 0.01);
+        assert!(bbox.x.min.is_infinite() && bbox.y.max.is_infinite());
+        let tilted = Plane::new(Point3::default(), Vector3::new([0.0, 1.0, 1.0]));
+        let bbox = tilted.bounding_box();
+        assert!((0..3).all(|n| bbox.axis_interval(n).size().is_infinite()));
+    }
+
+    #[test]
+    fn tangent_frame_is_orthonormal() {
+        let mut rng = Rng::new(5);
+        let axes = [
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, -1.0],
+            [1.0, 0.0, 0.0],
+            [0.0, -1.0, 0.0],
+        ];
+        let random = (0..100).map(|_| [(); 3].map(|_| rng.random_range(-1.0, 1.0)));
+        for normal in axes.into_iter().chain(random) {
+            let normal = Vector3::new(normal).unit_vector();
+            let (tangent, bitangent) = tangent_frame(normal);
+            for (a, b) in [(tangent, bitangent), (tangent, normal), (bitangent, normal)] {
+                assert!(a.dot(b).abs() < 1e-12);
+            }
+            assert!((tangent.len() - 1.0).abs() < 1e-12);
+            assert!((bitangent.len() - 1.0).abs() < 1e-12);
+            // right-handed, like x, y and z
+            assert!((tangent.cross(bitangent).dot(normal) - 1.0).abs() < 1e-12);
+        }
+    }
+}
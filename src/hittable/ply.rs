@@ -0,0 +1,699 @@
+//! Stanford PLY reader, for the ascii and both binary formats.
+
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    Double,
+    color::RGB,
+    vec3::{Point3, Vector3},
+};
+
+use super::mesh::{Face, LoadError, MeshBuffers, TriangleMesh};
+
+/// Read the `.ply` file at `path`.
+pub fn load_ply(path: impl AsRef<Path>) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let buffers = parse_ply(&data).map_err(|err| err.in_file(path))?;
+    TriangleMesh::new(buffers).map_err(|err| err.in_file(path))
+}
+
+/// Parse the contents of a PLY file.
+///
+/// Reads the `x y z`, `nx ny nz`, `u v` (or `s t`) and `red green blue` vertex
+/// properties, and the `vertex_indices` list of the faces, which are triangulated
+/// as a fan. Other elements and properties are skipped.
+///
+/// Errors in the header and in ascii data carry the line number,
+/// errors in binary data the byte offset.
+pub fn parse_ply(data: &[u8]) -> Result<MeshBuffers, LoadError> {
+    let header = Header::parse(data)?;
+    let body = &data[header.length..];
+    let mut reader: Box<dyn ValueReader> = match header.format {
+        Format::Ascii => Box::new(AsciiReader::new(body, header.lines)?),
+        Format::BinaryLittleEndian => Box::new(BinaryReader::new(body, header.length, false)),
+        Format::BinaryBigEndian => Box::new(BinaryReader::new(body, header.length, true)),
+    };
+
+    // the first vertex element holds the vertices, whatever order the elements come in
+    let vertex_element = header
+        .elements
+        .iter()
+        .position(|element| element.name == "vertex");
+    let (vertex_count, layout) = match vertex_element {
+        Some(i) => {
+            let element = &header.elements[i];
+            (element.count, VertexLayout::new(element))
+        }
+        None => (0, VertexLayout::default()),
+    };
+    let mut buffers = MeshBuffers::default();
+    for (i, element) in header.elements.iter().enumerate() {
+        match element.name.as_str() {
+            "vertex" if Some(i) == vertex_element => {
+                read_vertices(element, &layout, reader.as_mut(), &mut buffers)?
+            }
+            "face" => read_faces(
+                element,
+                &layout,
+                reader.as_mut(),
+                vertex_count,
+                &mut buffers,
+            )?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        skip_property(property, reader.as_mut())?;
+                    }
+                }
+            }
+        }
+    }
+    // the faces may come before the vertices, so their normals are checked at the end
+    for face in &mut buffers.faces {
+        face.normals = face.normals.filter(|normals| {
+            normals
+                .iter()
+                .all(|&n| !buffers.normals[n as usize].near_zero())
+        });
+    }
+    Ok(buffers)
+}
+
+/// Which properties of the vertex element hold the attributes the mesh keeps.
+#[derive(Clone, Copy, Default)]
+struct VertexLayout {
+    position: [Option<usize>; 3],
+    normal: [Option<usize>; 3],
+    uv: [Option<usize>; 3],
+    color: [Option<usize>; 3],
+}
+
+impl VertexLayout {
+    fn new(element: &Element) -> Self {
+        let slot = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|property| match property {
+                    Property::Scalar { name, .. } => names.contains(&name.as_str()),
+                    Property::List { .. } => false,
+                })
+        };
+        let find = |names: [&[&str]; 3]| names.map(slot);
+        Self {
+            position: find([&["x"], &["y"], &["z"]]),
+            normal: find([&["nx"], &["ny"], &["nz"]]),
+            uv: find([
+                &["u", "s", "texture_u", "texture_s"],
+                &["v", "t", "texture_v", "texture_t"],
+                &[],
+            ]),
+            color: find([
+                &["red", "r", "diffuse_red"],
+                &["green", "g", "diffuse_green"],
+                &["blue", "b", "diffuse_blue"],
+            ]),
+        }
+    }
+    fn has_normals(&self) -> bool {
+        self.normal.iter().all(Option::is_some)
+    }
+    fn has_uvs(&self) -> bool {
+        self.uv[0].is_some() && self.uv[1].is_some()
+    }
+    fn has_colors(&self) -> bool {
+        self.color.iter().all(Option::is_some)
+    }
+}
+
+fn read_vertices(
+    element: &Element,
+    layout: &VertexLayout,
+    reader: &mut dyn ValueReader,
+    buffers: &mut MeshBuffers,
+) -> Result<(), LoadError> {
+    let VertexLayout {
+        position,
+        normal,
+        uv,
+        color,
+    } = *layout;
+    let (has_normals, has_uvs, has_colors) =
+        (layout.has_normals(), layout.has_uvs(), layout.has_colors());
+
+    let mut values = vec![0.0; element.properties.len()];
+    for _ in 0..element.count {
+        for (value, property) in values.iter_mut().zip(&element.properties) {
+            *value = match property {
+                Property::Scalar { kind, .. } => reader.read(*kind)?,
+                Property::List { .. } => {
+                    skip_property(property, reader)?;
+                    0.0
+                }
+            };
+        }
+        let get = |slot: Option<usize>| slot.map_or(0.0, |i| values[i]);
+        buffers.positions.push(Point3::new(position.map(get)));
+        if has_normals {
+            // a zero normal stays zero, its faces use the geometric normal
+            let normal = Vector3::new(normal.map(get));
+            buffers.normals.push(if normal.near_zero() {
+                Vector3::default()
+            } else {
+                normal.unit_vector()
+            });
+        }
+        if has_uvs {
+            buffers.uvs.push([get(uv[0]), get(uv[1])]);
+        }
+        if has_colors {
+            // integer colors span the range of their type
+            let channel = |slot: Option<usize>| match slot.map(|i| &element.properties[i]) {
+                Some(Property::Scalar { kind, .. }) => get(slot) / kind.color_scale(),
+                _ => 0.0,
+            };
+            buffers.colors.push(RGB::new(color.map(channel)));
+        }
+    }
+    Ok(())
+}
+
+fn read_faces(
+    element: &Element,
+    layout: &VertexLayout,
+    reader: &mut dyn ValueReader,
+    vertex_count: usize,
+    buffers: &mut MeshBuffers,
+) -> Result<(), LoadError> {
+    let indices_property = element.properties.iter().position(|property| {
+        matches!(property, Property::List { name, .. } if name == "vertex_indices" || name == "vertex_index")
+    });
+    let Some(indices_property) = indices_property else {
+        return Err(LoadError::parse(
+            reader.location(),
+            "face element has no vertex_indices list",
+        ));
+    };
+    let (has_normals, has_uvs) = (layout.has_normals(), layout.has_uvs());
+    let mut indices = Vec::new();
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            if i != indices_property {
+                skip_property(property, reader)?;
+                continue;
+            }
+            let Property::List { count, item, .. } = property else {
+                unreachable!("vertex_indices is a list property");
+            };
+            let location = reader.location();
+            let length = reader.read(*count)? as usize;
+            indices.clear();
+            for _ in 0..length {
+                let index = reader.read(*item)?;
+                if index < 0.0 || index as usize >= vertex_count {
+                    return Err(LoadError::parse(
+                        location,
+                        format!("vertex index {} out of range", index),
+                    ));
+                }
+                indices.push(index as u32);
+            }
+            if length < 3 {
+                return Err(LoadError::parse(location, "face needs at least 3 vertices"));
+            }
+            for i in 1..length - 1 {
+                let positions = [indices[0], indices[i], indices[i + 1]];
+                buffers.faces.push(Face {
+                    positions,
+                    // vertex attributes share the indices of the positions
+                    normals: has_normals.then_some(positions),
+                    uvs: has_uvs.then_some(positions),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn skip_property(property: &Property, reader: &mut dyn ValueReader) -> Result<(), LoadError> {
+    match property {
+        Property::Scalar { kind, .. } => {
+            reader.read(*kind)?;
+        }
+        Property::List { count, item, .. } => {
+            let length = reader.read(*count)? as usize;
+            for _ in 0..length {
+                reader.read(*item)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ScalarKind {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarKind {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => ScalarKind::I8,
+            "uchar" | "uint8" => ScalarKind::U8,
+            "short" | "int16" => ScalarKind::I16,
+            "ushort" | "uint16" => ScalarKind::U16,
+            "int" | "int32" => ScalarKind::I32,
+            "uint" | "uint32" => ScalarKind::U32,
+            "float" | "float32" => ScalarKind::F32,
+            "double" | "float64" => ScalarKind::F64,
+            _ => return None,
+        })
+    }
+    fn size(self) -> usize {
+        match self {
+            ScalarKind::I8 | ScalarKind::U8 => 1,
+            ScalarKind::I16 | ScalarKind::U16 => 2,
+            ScalarKind::I32 | ScalarKind::U32 | ScalarKind::F32 => 4,
+            ScalarKind::F64 => 8,
+        }
+    }
+    /// Value of full intensity when the property is a color channel.
+    fn color_scale(self) -> Double {
+        match self {
+            ScalarKind::U8 => u8::MAX as Double,
+            ScalarKind::U16 => u16::MAX as Double,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        kind: ScalarKind,
+    },
+    List {
+        name: String,
+        count: ScalarKind,
+        item: ScalarKind,
+    },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// Length in bytes, including the `end_header` line
+    length: usize,
+    /// Number of lines
+    lines: usize,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, LoadError> {
+        let mut format = None;
+        let mut elements: Vec<Element> = Vec::new();
+        let mut offset = 0;
+        let mut line_number = 0;
+        loop {
+            let Some(end) = data[offset..].iter().position(|&byte| byte == b'\n') else {
+                return Err(LoadError::parse(line_number + 1, "missing end_header"));
+            };
+            line_number += 1;
+            let line = std::str::from_utf8(&data[offset..offset + end])
+                .map_err(|_| LoadError::parse(line_number, "header is not valid text"))?;
+            offset += end + 1;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: &str| LoadError::parse(line_number, message);
+            if line_number == 1 {
+                if tokens != ["ply"] {
+                    return Err(error("not a PLY file"));
+                }
+                continue;
+            }
+            match tokens.as_slice() {
+                [] | ["comment", ..] | ["obj_info", ..] => {}
+                ["format", name, "1.0"] => {
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::BinaryLittleEndian,
+                        "binary_big_endian" => Format::BinaryBigEndian,
+                        _ => return Err(error("unknown format")),
+                    });
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count.parse().map_err(|_| error("invalid element count"))?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count, item, name] => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| error("property outside of an element"))?;
+                    let kind = |name| ScalarKind::parse(name).ok_or_else(|| error("unknown type"));
+                    let (count, item) = (kind(count)?, kind(item)?);
+                    if matches!(count, ScalarKind::F32 | ScalarKind::F64) {
+                        return Err(error("list length must be an integer"));
+                    }
+                    element.properties.push(Property::List {
+                        name: name.to_string(),
+                        count,
+                        item,
+                    });
+                }
+                ["property", kind, name] => {
+                    let element = elements
+                        .last_mut()
+                        .ok_or_else(|| error("property outside of an element"))?;
+                    element.properties.push(Property::Scalar {
+                        name: name.to_string(),
+                        kind: ScalarKind::parse(kind).ok_or_else(|| error("unknown type"))?,
+                    });
+                }
+                ["end_header"] => break,
+                _ => return Err(error("invalid header line")),
+            }
+        }
+        Ok(Self {
+            format: format.ok_or_else(|| LoadError::parse(line_number, "missing format"))?,
+            elements,
+            length: offset,
+            lines: line_number,
+        })
+    }
+}
+
+/// Source of the property values of the body.
+trait ValueReader {
+    fn read(&mut self, kind: ScalarKind) -> Result<Double, LoadError>;
+    /// Position for error messages, a line number or byte offset.
+    fn location(&self) -> usize;
+}
+
+struct AsciiReader<'a> {
+    /// Tokens with their line numbers
+    tokens: Vec<(usize, &'a str)>,
+    next: usize,
+    last_line: usize,
+}
+
+impl<'a> AsciiReader<'a> {
+    fn new(body: &'a [u8], header_lines: usize) -> Result<Self, LoadError> {
+        let text = std::str::from_utf8(body)
+            .map_err(|_| LoadError::parse(header_lines + 1, "ascii data is not valid text"))?;
+        let tokens = text
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let line_number = header_lines + 1 + i;
+                line.split_whitespace()
+                    .map(move |token| (line_number, token))
+            })
+            .collect();
+        Ok(Self {
+            tokens,
+            next: 0,
+            last_line: header_lines,
+        })
+    }
+}
+
+impl ValueReader for AsciiReader<'_> {
+    fn read(&mut self, _kind: ScalarKind) -> Result<Double, LoadError> {
+        let Some(&(line, token)) = self.tokens.get(self.next) else {
+            return Err(LoadError::parse(
+                self.last_line + 1,
+                "unexpected end of data",
+            ));
+        };
+        self.next += 1;
+        self.last_line = line;
+        token
+            .parse()
+            .map_err(|_| LoadError::parse(line, format!("invalid number `{}`", token)))
+    }
+    fn location(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.last_line, |&(line, _)| line)
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    /// Offset of `data` in the file
+    start: usize,
+    big_endian: bool,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(data: &'a [u8], start: usize, big_endian: bool) -> Self {
+        Self {
+            data,
+            position: 0,
+            start,
+            big_endian,
+        }
+    }
+}
+
+impl ValueReader for BinaryReader<'_> {
+    fn read(&mut self, kind: ScalarKind) -> Result<Double, LoadError> {
+        let size = kind.size();
+        let Some(bytes) = self.data.get(self.position..self.position + size) else {
+            return Err(LoadError::parse(self.location(), "unexpected end of data"));
+        };
+        self.position += size;
+        let mut buf = [0; 8];
+        buf[..size].copy_from_slice(bytes);
+        if self.big_endian {
+            buf[..size].reverse();
+        }
+        // buf now holds the value in little endian order
+        Ok(match kind {
+            ScalarKind::I8 => buf[0] as i8 as Double,
+            ScalarKind::U8 => buf[0] as Double,
+            ScalarKind::I16 => i16::from_le_bytes([buf[0], buf[1]]) as Double,
+            ScalarKind::U16 => u16::from_le_bytes([buf[0], buf[1]]) as Double,
+            ScalarKind::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as Double,
+            ScalarKind::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as Double,
+            ScalarKind::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as Double,
+            ScalarKind::F64 => f64::from_le_bytes(buf),
+        })
+    }
+    fn location(&self) -> usize {
+        self.start + self.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "ply
+format ascii 1.0
+comment a unit square
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0 1 255 0 0
+1 0 0 0 0 1 0 255 0
+1 1 0 0 0 1 0 0 255
+0 1 0 0 0 1 255 255 255
+4 0 1 2 3
+";
+
+    fn assert_square(buffers: &MeshBuffers) {
+        let positions: Vec<_> = buffers.positions.iter().map(|p| p.0).collect();
+        assert_eq!(
+            positions,
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0]
+            ]
+        );
+        let faces: Vec<_> = buffers.faces.iter().map(|face| face.positions).collect();
+        assert_eq!(faces, [[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn ascii_square_with_normals_and_colors() {
+        let buffers = parse_ply(ASCII.as_bytes()).unwrap();
+        assert_square(&buffers);
+        assert_eq!(buffers.normals.len(), 4);
+        assert_eq!(buffers.faces[1].normals, Some([0, 2, 3]));
+        assert_eq!(buffers.faces[1].uvs, None);
+        assert_eq!(buffers.colors[1].0, [0.0, 1.0, 0.0]);
+        assert_eq!(buffers.colors[3].0, [1.0; 3]);
+    }
+
+    #[test]
+    fn zero_normals_fall_back_to_the_face_normal() {
+        // the third vertex has no usable normal
+        let source = ASCII.replace("1 1 0 0 0 1 0 0 255", "1 1 0 0 0 0 0 0 255");
+        let buffers = parse_ply(source.as_bytes()).unwrap();
+        assert_square(&buffers);
+        assert_eq!(buffers.normals[2].0, [0.0; 3]);
+        assert_eq!(buffers.faces[0].normals, None);
+        assert_eq!(buffers.faces[1].normals, None);
+
+        let source = ASCII.replace("0 1 0 0 0 1 255 255 255", "0 1 0 0 0 0 255 255 255");
+        let buffers = parse_ply(source.as_bytes()).unwrap();
+        assert_eq!(buffers.faces[0].normals, Some([0, 1, 2]));
+        assert_eq!(buffers.faces[1].normals, None);
+    }
+
+    /// The square with `u v` coordinates, in binary with the given byte order.
+    fn binary(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut data = format!(
+            "ply\nformat {format} 1.0\nelement vertex 4\nproperty double x\nproperty double y\n\
+             property double z\nproperty float u\nproperty float v\nelement face 1\n\
+             property list uchar ushort vertex_indices\nend_header\n"
+        )
+        .into_bytes();
+        let corners = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+        for [x, y] in corners {
+            for value in [x, y, 0.0] {
+                let bytes = if big_endian {
+                    f64::to_be_bytes(value)
+                } else {
+                    f64::to_le_bytes(value)
+                };
+                data.extend_from_slice(&bytes);
+            }
+            for value in [x as f32, y as f32] {
+                let bytes = if big_endian {
+                    value.to_be_bytes()
+                } else {
+                    value.to_le_bytes()
+                };
+                data.extend_from_slice(&bytes);
+            }
+        }
+        data.push(4);
+        for index in 0..4u16 {
+            let bytes = if big_endian {
+                index.to_be_bytes()
+            } else {
+                index.to_le_bytes()
+            };
+            data.extend_from_slice(&bytes);
+        }
+        data
+    }
+
+    #[test]
+    fn binary_squares_in_both_byte_orders() {
+        for big_endian in [false, true] {
+            let buffers = parse_ply(&binary(big_endian)).unwrap();
+            assert_square(&buffers);
+            assert_eq!(buffers.uvs[2], [1.0, 1.0]);
+            assert_eq!(buffers.faces[0].uvs, Some([0, 1, 2]));
+            assert_eq!(buffers.faces[0].normals, None);
+        }
+    }
+
+    #[test]
+    fn truncated_binary_data_reports_the_offset() {
+        let data = binary(false);
+        let header_length = data.len() - 4 * 32 - 9;
+        match parse_ply(&data[..data.len() - 1]) {
+            Err(LoadError::Parse { line, .. }) => assert_eq!(line, header_length + 4 * 32 + 7),
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn faces_before_vertices_get_the_vertex_attributes() {
+        let source = "ply
+format ascii 1.0
+element face 1
+property list uchar int vertex_indices
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float s
+property float t
+element vertex 2
+property float x
+property float y
+property float z
+end_header
+3 0 1 2
+0 0 0 0 0 1 0 0
+1 0 0 0 0 1 1 0
+0 1 0 0 0 1 0 1
+5 5 5
+6 6 6
+";
+        let buffers = parse_ply(source.as_bytes()).unwrap();
+        // the second vertex element is skipped
+        assert_eq!(buffers.positions.len(), 3);
+        assert_eq!(buffers.normals.len(), 3);
+        assert_eq!(buffers.uvs.len(), 3);
+        assert_eq!(buffers.faces[0].normals, Some([0, 1, 2]));
+        assert_eq!(buffers.faces[0].uvs, Some([0, 1, 2]));
+    }
+
+    #[test]
+    fn ascii_errors_report_their_line() {
+        let line_of = |source: &str| match parse_ply(source.as_bytes()) {
+            Err(LoadError::Parse { line, .. }) => line,
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        };
+        assert_eq!(line_of(&ASCII.replace("4 0 1 2 3", "4 0 1 2 9")), 21);
+        assert_eq!(line_of(&ASCII.replace("1 1 0 0 0 1", "1 x 0 0 0 1")), 19);
+        assert_eq!(
+            line_of(&ASCII.replace("property uchar red", "property bogus red")),
+            11
+        );
+        assert_eq!(line_of(&ASCII.replace("4 0 1 2 3\n", "")), 21);
+        assert_eq!(line_of("plx\n"), 1);
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    sampler::Sampler,
+    vec3::{Point3, Vector3},
+};
+
+use super::{HitRecord, SHADOW_ACNE_EPSILON};
+
+/// Parallelogram with a corner at `q` and the edges `u` and `v`.
+///
+/// The front side, where the normal `u x v` points to, sees `u` and `v` counter-clockwise.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vector3,
+    pub v: Vector3,
+    pub material: SharedMaterial,
+    /// Unit normal of the plane containing the quad
+    normal: Vector3,
+    /// `u x v / |u x v|^2`, turns a point of the plane into its coordinates along `u` and `v`
+    w: Vector3,
+}
+
+impl Hittable for Quad {
+    // the plane is normal dot P = normal dot Q, put in the ray P(t) = O + t*d
+    // t = (normal dot Q - normal dot O) / (normal dot d)
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        // the ray is parallel to the plane
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+        let t = self.normal.dot(self.q - ray.origin) / denominator;
+        if !ray_t_range.surrounds(t) {
+            return None;
+        }
+        let point = ray.at(t);
+        // P - Q = alpha * u + beta * v
+        let planar = point - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+        let record =
+            HitRecord::new(ray, t, point, self.normal, self.material.as_ref()).with_uv(alpha, beta);
+        Some(record)
+    }
+    fn bounding_box(&self) -> Aabb {
+        let diagonal = Aabb::from_points(self.q, self.q + self.u + self.v);
+        let other_diagonal = Aabb::from_points(self.q + self.u, self.q + self.v);
+        Aabb::enclosing(diagonal, other_diagonal)
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> Double {
+        let ray = Ray::new(origin, direction);
+        let Some(record) = self.hit(&ray, Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY)) else {
+            return 0.0;
+        };
+        // convert the area density 1/A to solid angle: distance^2 / (|cos θ| A)
+        let distance_squared = record.ray_t * record.ray_t * direction.len_squared();
+        let cosine = (direction.dot(self.normal) / direction.len()).abs();
+        distance_squared / (cosine * self.area())
+    }
+    /// Towards a point uniformly distributed over the area.
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let [alpha, beta] = sampler.get_2d();
+        let point = self.q + self.u * alpha + self.v * beta;
+        point - origin
+    }
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vector3, v: Vector3) -> Self {
+        let n = u.cross(v);
+        Self {
+            q,
+            u,
+            v,
+            material: default_material(),
+            normal: n.unit_vector(),
+            w: n / n.dot(n),
+        }
+    }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.material = material;
+        self
+    }
+    /// Area of the parallelogram.
+    pub fn area(&self) -> Double {
+        self.u.cross(self.v).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::tests::assert_light_sampling;
+
+    /// 2 x 1 quad on the xy plane from the origin, facing +z.
+    fn quad() -> Quad {
+        Quad::new(
+            Point3::default(),
+            Vector3::default().with_x(2.0),
+            Vector3::default().with_y(1.0),
+        )
+    }
+
+    fn towards(from: [Double; 3], to: [Double; 3]) -> Ray {
+        let origin = Point3::new(from);
+        Ray::new(origin, Point3::new(to) - origin)
+    }
+
+    #[test]
+    fn hits_inside_the_edges() {
+        let quad = quad();
+        let range = Interval::new(0.001, f64::INFINITY);
+        let record = quad
+            .hit(&towards([0.5, 0.5, 2.0], [0.5, 0.5, 0.0]), range)
+            .unwrap();
+        assert!((record.ray_t - 1.0).abs() < 1e-12);
+        assert_eq!(record.uv, [0.25, 0.5]);
+        for outside in [
+            [-0.1, 0.5, 0.0],
+            [2.1, 0.5, 0.0],
+            [1.0, -0.1, 0.0],
+            [1.0, 1.1, 0.0],
+        ] {
+            assert!(
+                quad.hit(&towards([1.0, 0.5, 2.0], outside), range)
+                    .is_none()
+            );
+        }
+        // parallel to the plane
+        assert!(
+            quad.hit(&towards([-1.0, 0.5, 0.0], [1.0, 0.5, 0.0]), range)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn hits_only_within_the_t_range() {
+        let quad = quad();
+        let ray = towards([1.0, 0.5, 2.0], [1.0, 0.5, 1.0]);
+        assert!(quad.hit(&ray, Interval::new(0.001, 1.9)).is_none());
+        assert!(quad.hit(&ray, Interval::new(0.001, 2.1)).is_some());
+        assert!(quad.hit(&ray, Interval::new(2.1, f64::INFINITY)).is_none());
+        // behind the origin
+        let away = towards([1.0, 0.5, 2.0], [1.0, 0.5, 3.0]);
+        assert!(
+            quad.hit(&away, Interval::new(0.001, f64::INFINITY))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn front_side_is_counter_clockwise() {
+        let quad = quad();
+        let range = Interval::new(0.001, f64::INFINITY);
+        let front = quad
+            .hit(&towards([1.0, 0.5, 1.0], [1.0, 0.5, 0.0]), range)
+            .unwrap();
+        assert!(front.normal_direction.is_outward());
+        assert_eq!(front.normal, Vector3::new([0.0, 0.0, 1.0]));
+        let back = quad
+            .hit(&towards([1.0, 0.5, -1.0], [1.0, 0.5, 0.0]), range)
+            .unwrap();
+        assert!(!back.normal_direction.is_outward());
+        assert_eq!(back.normal, Vector3::new([0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn bounding_box_holds_the_corners() {
+        let quad = Quad::new(
+            Point3::new([1.0, 0.0, 0.0]),
+            Vector3::new([-1.0, 1.0, 0.0]),
+            Vector3::new([0.0, 0.0, 2.0]),
+        );
+        let bbox = quad.bounding_box();
+        assert_eq!([bbox.x.min, bbox.x.max], [0.0, 1.0]);
+        assert_eq!([bbox.y.min, bbox.y.max], [0.0, 1.0]);
+        assert_eq!([bbox.z.min, bbox.z.max], [0.0, 2.0]);
+    }
+
+    #[test]
+    fn light_sampling() {
+        assert_light_sampling(&quad(), Point3::new([0.5, 0.3, 1.0]));
+        // from behind, and from off to the side
+        assert_light_sampling(&quad(), Point3::new([1.0, 0.5, -0.5]));
+        assert_light_sampling(&quad(), Point3::new([3.0, -0.5, 0.5]));
+    }
+}
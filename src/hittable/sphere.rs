@@ -1,10 +1,23 @@
-use crate::{Array3, Double, hittable::Hittable, interval::Interval, ray::Ray, vec3::Point3};
+use std::f64::consts::PI;
+use std::sync::Arc;
 
-use super::HitRecord;
+use crate::{
+    Array3, Double,
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    sampler::Sampler,
+    vec3::{Point3, Vector3},
+};
+
+use super::{HitRecord, SHADOW_ACNE_EPSILON, plane::tangent_frame};
 
 pub struct Sphere {
     pub center: Point3,
     pub radius: Double,
+    pub material: SharedMaterial,
 }
 
 impl Hittable for Sphere {
@@ -33,7 +46,7 @@ impl Hittable for Sphere {
     // 如果 射线离开 ,t2=( -b + sqrt(b^2 - 4ac) )/2a
     // 如果 相切 t1=t2
     // 取最近的t, 即t1
-    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
         // the vector from ray_origin to sphere_center OC
         let oc = self.center - ray.origin;
         //
@@ -60,10 +73,52 @@ impl Hittable for Sphere {
         let point = ray.at(root);
         // unit vector: vector(P-C)/len(radius)
         let outward_normal = (point - self.center) / self.radius;
-        let record = HitRecord::new(ray, root, point, outward_normal);
+        let [u, v] = Self::uv(outward_normal);
+        let record = HitRecord::new(ray, root, point, outward_normal, self.material.as_ref())
+            .with_uv(u, v);
 
         Some(record)
     }
+    fn bounding_box(&self) -> Aabb {
+        let radius = Vector3::new([self.radius; 3]);
+        Aabb::from_points(self.center + (-radius), self.center + radius)
+    }
+    fn pdf_value(&self, origin: Point3, direction: Vector3) -> Double {
+        let ray = Ray::new(origin, direction);
+        if self
+            .hit(&ray, Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY))
+            .is_none()
+        {
+            return 0.0;
+        }
+        let distance_squared = (self.center - origin).len_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            // seen from inside, the sphere covers every direction
+            return 1.0 / (4.0 * PI);
+        }
+        // the sphere fills a cone of half angle θmax, with sin θmax = radius / distance
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+    /// Uniform over the cone of directions the sphere covers.
+    fn random(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vector3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.len_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return Vector3::random_unit(sampler);
+        }
+        // cos θ is uniform in [cos θmax, 1] for a uniform solid angle
+        let [u, v] = sampler.get_2d();
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let z = 1.0 + v * (cos_theta_max - 1.0);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u;
+        let axis = direction.unit_vector();
+        let (tangent, bitangent) = tangent_frame(axis);
+        tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + axis * z
+    }
 }
 
 impl Sphere {
@@ -71,22 +126,41 @@ impl Sphere {
         Self {
             center: center.into(),
             radius: Default::default(),
+            material: default_material(),
         }
     }
     pub fn from_point3(center: Point3) -> Self {
         Self {
             center,
             radius: Default::default(),
+            material: default_material(),
         }
     }
+    /// Texture coordinates of a point on the unit sphere centered at the origin:
+    /// u is the angle around the Y axis from X=-1, v the angle from Y=-1 to Y=+1, both in [0,1].
+    fn uv(p: Vector3) -> [Double; 2] {
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + PI;
+        [phi / (2.0 * PI), theta / PI]
+    }
     pub fn with_radius(mut self,radius: Double) ->Self {
         self.radius=radius;
         self
     }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.material = material;
+        self
+    }
     pub fn new(center: Array3, radius: Double) -> Self {
         Self {
             center: center.into(),
             radius: radius.max(0.0),
+            material: default_material(),
         }
     }
 }
+
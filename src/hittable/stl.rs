@@ -0,0 +1,502 @@
+//! STL reader, for both the binary and ascii formats.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    Double,
+    vec3::{Point3, Vector3},
+};
+
+use super::mesh::{Face, LoadError, MeshBuffers, TriangleMesh};
+
+/// How the triangle soup of an STL file is turned into a mesh.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StlOptions {
+    /// Merge vertices closer than this distance
+    pub weld_tolerance: Option<Double>,
+    /// Smooth the normals across edges whose faces meet at less than this angle, in degrees
+    pub crease_angle: Option<Double>,
+}
+
+impl StlOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_weld_tolerance(mut self, tolerance: Double) -> Self {
+        self.weld_tolerance = Some(tolerance);
+        self
+    }
+    /// Smoothing needs the faces to share vertices, so without a weld tolerance
+    /// the vertices at exactly the same position are merged.
+    pub fn with_crease_angle(mut self, degrees: Double) -> Self {
+        self.crease_angle = Some(degrees);
+        self
+    }
+}
+
+/// Read the `.stl` file at `path`.
+pub fn load_stl(path: impl AsRef<Path>, options: StlOptions) -> Result<TriangleMesh, LoadError> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let buffers = parse_stl(&data, options).map_err(|err| err.in_file(path))?;
+    TriangleMesh::new(buffers).map_err(|err| err.in_file(path))
+}
+
+/// Parse the contents of an STL file.
+///
+/// Errors in ascii files carry the line number, in binary files the byte offset.
+pub fn parse_stl(data: &[u8], options: StlOptions) -> Result<MeshBuffers, LoadError> {
+    let facets = parse_facets(data)?;
+    let tolerance = match (options.weld_tolerance, options.crease_angle) {
+        (Some(tolerance), _) => Some(tolerance),
+        (None, Some(_)) => Some(0.0),
+        (None, None) => None,
+    };
+    let mut buffers = match tolerance {
+        Some(tolerance) => weld(&facets, tolerance),
+        None => unwelded(&facets),
+    };
+    if let Some(crease_angle) = options.crease_angle {
+        smooth(&mut buffers, crease_angle);
+    }
+    Ok(buffers)
+}
+
+/// A triangle with its normal, as stored in the file.
+struct Facet {
+    normal: Vector3,
+    vertices: [Point3; 3],
+}
+
+impl Facet {
+    /// The stored normal, or the one of the vertices if it was left out.
+    ///
+    /// The winding decides the front side, so a stored normal pointing to the
+    /// back is replaced too, it would turn the shading inside out.
+    fn normal(&self) -> Vector3 {
+        let [a, b, c] = self.vertices;
+        let winding = (b - a).cross(c - a);
+        if self.normal.near_zero() || self.normal.dot(winding) < 0.0 {
+            if winding.near_zero() {
+                winding
+            } else {
+                winding.unit_vector()
+            }
+        } else {
+            self.normal.unit_vector()
+        }
+    }
+}
+
+/// Binary files have an 80 byte header, a triangle count and 50 bytes per triangle.
+///
+/// Some binary files start with `solid` too, and some exporters pad them, so a file
+/// starting with `solid` is only taken as ascii if it has the wrong size for a binary
+/// one, or if it parses as ascii.
+fn parse_facets(data: &[u8]) -> Result<Vec<Facet>, LoadError> {
+    let binary_length = binary_length(data);
+    if !data.trim_ascii_start().starts_with(b"solid") || binary_length == Some(data.len()) {
+        return parse_binary(data);
+    }
+    match parse_ascii(data) {
+        Ok(facets) => Ok(facets),
+        Err(_) if binary_length.is_some_and(|length| length <= data.len()) => parse_binary(data),
+        Err(err) => Err(err),
+    }
+}
+
+/// Size of a binary file holding the triangle count found in the header.
+fn binary_length(data: &[u8]) -> Option<usize> {
+    let count = data.get(80..84)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    count.checked_mul(50)?.checked_add(84)
+}
+
+fn parse_binary(data: &[u8]) -> Result<Vec<Facet>, LoadError> {
+    if data.len() < 84 {
+        return Err(LoadError::parse(
+            data.len(),
+            "file too short for a binary STL",
+        ));
+    }
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    if !binary_length(data).is_some_and(|expected| expected <= data.len()) {
+        return Err(LoadError::parse(
+            data.len(),
+            format!("{} triangles need more than {} bytes", count, data.len()),
+        ));
+    }
+    let read = |offset: usize| -> [Double; 3] {
+        [0, 4, 8].map(|i| {
+            let bytes = &data[offset + i..offset + i + 4];
+            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as Double
+        })
+    };
+    Ok((0..count)
+        .map(|i| {
+            // normal, three vertices and a 2 byte attribute
+            let offset = 84 + i * 50;
+            Facet {
+                normal: Vector3::new(read(offset)),
+                vertices: [1, 2, 3].map(|v| Point3::new(read(offset + v * 12))),
+            }
+        })
+        .collect())
+}
+
+fn parse_ascii(data: &[u8]) -> Result<Vec<Facet>, LoadError> {
+    let text = std::str::from_utf8(data).map_err(|_| LoadError::parse(1, "invalid text"))?;
+    let mut facets = Vec::new();
+    let mut normal = Vector3::default();
+    let mut vertices = Vec::with_capacity(3);
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let numbers = |tokens: &[&str]| -> Result<[Double; 3], LoadError> {
+            let mut values = [0.0; 3];
+            if tokens.len() != 3 {
+                return Err(LoadError::parse(line_number, "expected 3 numbers"));
+            }
+            for (value, token) in values.iter_mut().zip(tokens) {
+                *value = token.parse().map_err(|_| {
+                    LoadError::parse(line_number, format!("invalid number `{}`", token))
+                })?;
+            }
+            Ok(values)
+        };
+        match tokens.as_slice() {
+            ["facet", "normal", rest @ ..] => {
+                normal = Vector3::new(numbers(rest)?);
+                vertices.clear();
+            }
+            ["vertex", rest @ ..] => {
+                if vertices.len() == 3 {
+                    return Err(LoadError::parse(
+                        line_number,
+                        "facet has more than 3 vertices",
+                    ));
+                }
+                vertices.push(Point3::new(numbers(rest)?));
+            }
+            ["endfacet"] => {
+                let [a, b, c] = vertices[..] else {
+                    return Err(LoadError::parse(line_number, "facet needs 3 vertices"));
+                };
+                facets.push(Facet {
+                    normal,
+                    vertices: [a, b, c],
+                });
+            }
+            [] | ["solid", ..] | ["endsolid", ..] | ["outer", "loop"] | ["endloop"] => {}
+            [keyword, ..] => {
+                return Err(LoadError::parse(
+                    line_number,
+                    format!("unexpected `{}`", keyword),
+                ));
+            }
+        }
+    }
+    Ok(facets)
+}
+
+/// Every facet keeps its own three vertices and flat normal.
+fn unwelded(facets: &[Facet]) -> MeshBuffers {
+    let mut buffers = MeshBuffers::default();
+    for facet in facets {
+        let first = buffers.positions.len() as u32;
+        buffers.positions.extend(facet.vertices);
+        let normal = buffers.normals.len() as u32;
+        buffers.normals.push(facet.normal());
+        buffers.faces.push(Face {
+            positions: [first, first + 1, first + 2],
+            normals: Some([normal; 3]),
+            ..Default::default()
+        });
+    }
+    buffers
+}
+
+/// Merge the vertices closer than `tolerance`, dropping the faces which collapse.
+fn weld(facets: &[Facet], tolerance: Double) -> MeshBuffers {
+    let mut buffers = MeshBuffers::default();
+    // vertices bucketed on a grid of `tolerance` sized cells, or by exact position
+    let cell_size = if tolerance > 0.0 { tolerance } else { 1.0 };
+    let cell = |p: Point3| p.map(|x| (x / cell_size).floor() as i64);
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let mut index_of = |positions: &mut Vec<Point3>, p: Point3| -> u32 {
+        let [x, y, z] = cell(p);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(bucket) = grid.get(&[x + dx, y + dy, z + dz]) else {
+                        continue;
+                    };
+                    let found = bucket
+                        .iter()
+                        .find(|&&i| (positions[i as usize] - p).len() <= tolerance);
+                    if let Some(&i) = found {
+                        return i;
+                    }
+                }
+            }
+        }
+        positions.push(p);
+        let index = positions.len() as u32 - 1;
+        grid.entry([x, y, z]).or_default().push(index);
+        index
+    };
+    for facet in facets {
+        let [a, b, c] = facet.vertices.map(|p| index_of(&mut buffers.positions, p));
+        if a == b || b == c || c == a {
+            continue;
+        }
+        let normal = buffers.normals.len() as u32;
+        buffers.normals.push(facet.normal());
+        buffers.faces.push(Face {
+            positions: [a, b, c],
+            normals: Some([normal; 3]),
+            ..Default::default()
+        });
+    }
+    buffers
+}
+
+/// Replace the facet normals by the area weighted average of the normals of
+/// the faces around each vertex, unless they meet at more than `crease_angle`.
+fn smooth(buffers: &mut MeshBuffers, crease_angle: Double) {
+    let cos_crease = crease_angle.to_radians().cos();
+    // twice the area, pointing along the normal
+    let weighted: Vec<Vector3> = buffers
+        .faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.positions.map(|i| buffers.positions[i as usize]);
+            (b - a).cross(c - a)
+        })
+        .collect();
+    let facet_normals: Vec<Vector3> = buffers
+        .faces
+        .iter()
+        .map(|face| buffers.normals[face.normals.map_or(0, |n| n[0]) as usize])
+        .collect();
+    let mut faces_around = vec![Vec::new(); buffers.positions.len()];
+    for (f, face) in buffers.faces.iter().enumerate() {
+        for i in face.positions {
+            faces_around[i as usize].push(f as u32);
+        }
+    }
+
+    let mut normals = Vec::with_capacity(buffers.faces.len() * 3);
+    for (f, face) in buffers.faces.iter_mut().enumerate() {
+        let facet_normal = facet_normals[f];
+        let corners = face.positions.map(|vertex| {
+            let sum = faces_around[vertex as usize]
+                .iter()
+                .filter(|&&g| facet_normals[g as usize].dot(facet_normal) >= cos_crease)
+                .fold(Vector3::default(), |sum, &g| sum + weighted[g as usize]);
+            let normal = if sum.near_zero() {
+                facet_normal
+            } else {
+                sum.unit_vector()
+            };
+            normals.push(normal);
+            normals.len() as u32 - 1
+        });
+        face.normals = Some(corners);
+    }
+    buffers.normals = normals;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary file with `header` and one triangle, followed by `padding` zero bytes.
+    fn binary_file(header: &[u8], padding: usize) -> Vec<u8> {
+        let mut data = header.to_vec();
+        data.resize(80, b' ');
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for value in [
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[0, 0]);
+        data.resize(data.len() + padding, 0);
+        data
+    }
+
+    fn positions(data: &[u8]) -> Vec<[Double; 3]> {
+        let buffers = parse_stl(data, StlOptions::new()).unwrap();
+        buffers.positions.iter().map(|p| p.0).collect()
+    }
+
+    const TRIANGLE: [[Double; 3]; 3] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
+    fn facet(vertices: [[Double; 3]; 3]) -> Facet {
+        Facet {
+            normal: Vector3::default(),
+            vertices: vertices.map(Point3::new),
+        }
+    }
+
+    /// Two triangles sharing the edge along the y axis, folded by `angle` degrees,
+    /// with the shared vertices of the second one moved by `offset`.
+    fn fold(angle: Double, offset: Double) -> [Facet; 2] {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        [
+            facet([[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]]),
+            facet([[offset, 1.0, 0.0], [offset, 0.0, 0.0], [cos, 0.0, sin]]),
+        ]
+    }
+
+    #[test]
+    fn welding_merges_vertices_within_the_tolerance() {
+        let facets = fold(0.0, 1e-5);
+        assert_eq!(unwelded(&facets).positions.len(), 6);
+        assert_eq!(weld(&facets, 1e-4).positions.len(), 4);
+        assert_eq!(weld(&facets, 1e-6).positions.len(), 6);
+        assert_eq!(weld(&facets, 0.0).positions.len(), 6);
+        assert_eq!(weld(&fold(0.0, 0.0), 0.0).positions.len(), 4);
+        let faces: Vec<_> = weld(&facets, 1e-4)
+            .faces
+            .iter()
+            .map(|f| f.positions)
+            .collect();
+        assert_eq!(faces, [[0, 1, 2], [1, 0, 3]]);
+    }
+
+    #[test]
+    fn welding_drops_collapsed_faces() {
+        let sliver = facet([[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1e-5, 0.0]]);
+        let [a, b] = fold(0.0, 0.0);
+        let facets = [a, b, sliver, facet(TRIANGLE)];
+        assert_eq!(weld(&facets, 1e-4).faces.len(), 3);
+        assert_eq!(weld(&facets, 1e-6).faces.len(), 4);
+    }
+
+    /// The normals of the corners of face `face` at the shared edge.
+    fn edge_normals(buffers: &MeshBuffers, face: usize) -> Vec<[Double; 3]> {
+        let Face {
+            positions, normals, ..
+        } = buffers.faces[face];
+        positions
+            .iter()
+            .zip(normals.unwrap())
+            .filter(|&(&p, _)| buffers.positions[p as usize].0[0].abs() < 1e-9)
+            .map(|(_, n)| buffers.normals[n as usize].0)
+            .collect()
+    }
+
+    fn assert_close(a: [Double; 3], b: [Double; 3]) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn smoothing_stops_at_the_crease_angle() {
+        let options = |crease: Double| StlOptions::new().with_crease_angle(crease);
+        let mut text = String::from("solid fold\n");
+        for facet in fold(30.0, 0.0) {
+            text += "facet normal 0 0 0\nouter loop\n";
+            for vertex in facet.vertices {
+                let [x, y, z] = vertex.0;
+                text += &format!("vertex {x} {y} {z}\n");
+            }
+            text += "endloop\nendfacet\n";
+        }
+        text += "endsolid fold\n";
+
+        // both faces have the same area, so the shared corners get the mean normal
+        let smooth = parse_stl(text.as_bytes(), options(45.0)).unwrap();
+        let (sin, cos) = 15.0_f64.to_radians().sin_cos();
+        for face in 0..2 {
+            for normal in edge_normals(&smooth, face) {
+                assert_close(normal, [-sin, 0.0, cos]);
+            }
+        }
+        // the corners away from the edge only see their own face
+        let [_, _, far] = smooth.faces[0].normals.unwrap();
+        assert_close(smooth.normals[far as usize].0, [0.0, 0.0, 1.0]);
+
+        let flat = parse_stl(text.as_bytes(), options(20.0)).unwrap();
+        for normal in edge_normals(&flat, 0) {
+            assert_close(normal, [0.0, 0.0, 1.0]);
+        }
+        let (sin, cos) = 30.0_f64.to_radians().sin_cos();
+        for normal in edge_normals(&flat, 1) {
+            assert_close(normal, [-sin, 0.0, cos]);
+        }
+    }
+
+    #[test]
+    fn stored_normals_against_the_winding_are_ignored() {
+        let mut reversed = facet(TRIANGLE);
+        reversed.normal = Vector3::new([0.0, 0.0, -1.0]);
+        let mut tilted = facet(TRIANGLE);
+        tilted.normal = Vector3::new([0.0, 1.0, 1.0]);
+        assert_close(reversed.normal().0, [0.0, 0.0, 1.0]);
+        assert_close(facet(TRIANGLE).normal().0, [0.0, 0.0, 1.0]);
+        let half = 0.5_f64.sqrt();
+        assert_close(tilted.normal().0, [0.0, half, half]);
+
+        let facets = [reversed];
+        for buffers in [unwelded(&facets), weld(&facets, 0.0)] {
+            assert_close(buffers.normals[0].0, [0.0, 0.0, 1.0]);
+        }
+        let mut buffers = weld(&facets, 0.0);
+        smooth(&mut buffers, 30.0);
+        for normal in &buffers.normals {
+            assert_close(normal.0, [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn binary() {
+        assert_eq!(positions(&binary_file(b"exported", 0)), TRIANGLE);
+        assert_eq!(positions(&binary_file(b"exported", 16)), TRIANGLE);
+    }
+
+    #[test]
+    fn binary_with_solid_header() {
+        assert_eq!(positions(&binary_file(b"solid part", 0)), TRIANGLE);
+        assert_eq!(positions(&binary_file(b"solid part", 7)), TRIANGLE);
+    }
+
+    #[test]
+    fn ascii() {
+        let text = "solid part
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid part
+";
+        assert_eq!(positions(text.as_bytes()), TRIANGLE);
+    }
+
+    #[test]
+    fn ascii_errors_keep_the_line() {
+        let text = "solid part\n  facet normal 0 0 1\n    outer loop\n      vertex 0 x 0\n";
+        match parse_stl(text.as_bytes(), StlOptions::new()) {
+            Err(LoadError::Parse { line, .. }) => assert_eq!(line, 4),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let mut data = binary_file(b"exported", 0);
+        data.truncate(100);
+        assert!(parse_stl(&data, StlOptions::new()).is_err());
+        data[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_stl(&data, StlOptions::new()).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use crate::{
+    Double,
+    aabb::Aabb,
+    hittable::Hittable,
+    interval::Interval,
+    material::{Material, SharedMaterial, default_material},
+    ray::Ray,
+    vec3::{Point3, Vector3},
+};
+
+use super::HitRecord;
+
+pub struct Triangle {
+    pub vertices: [Point3; 3],
+    /// Per-vertex normals for smooth shading, the face normal is used without them
+    pub normals: Option<[Vector3; 3]>,
+    /// Per-vertex texture coordinates, the barycentric coordinates are used without them
+    pub uvs: Option<[[Double; 2]; 3]>,
+    pub material: SharedMaterial,
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, ray_t_range: Interval) -> Option<HitRecord<'_>> {
+        let (t, barycentric) = intersect(ray, &self.vertices, ray_t_range)?;
+        let [a, b, c] = self.vertices;
+        let outward_normal = (b - a).cross(c - a).unit_vector();
+        let record = HitRecord::new(ray, t, ray.at(t), outward_normal, self.material.as_ref());
+        Some(shade(
+            record,
+            barycentric,
+            self.normals.as_ref(),
+            self.uvs.as_ref(),
+        ))
+    }
+    fn bounding_box(&self) -> Aabb {
+        let [a, b, c] = self.vertices;
+        Aabb::enclosing(Aabb::from_points(a, b), Aabb::from_points(c, c))
+    }
+}
+
+impl Triangle {
+    /// Triangle with the vertices in counter-clockwise order seen from the front.
+    pub fn new(a: Point3, b: Point3, c: Point3) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+            material: default_material(),
+        }
+    }
+    pub fn with_normals(mut self, normals: [Vector3; 3]) -> Self {
+        self.normals = Some(normals.map(|n| n.unit_vector()));
+        self
+    }
+    pub fn with_uvs(mut self, uvs: [[Double; 2]; 3]) -> Self {
+        self.uvs = Some(uvs);
+        self
+    }
+    pub fn with_material<M: Material + 'static>(self, material: M) -> Self {
+        self.with_shared_material(Arc::new(material))
+    }
+    /// Use a material which may be shared with other objects.
+    pub fn with_shared_material(mut self, material: SharedMaterial) -> Self {
+        self.material = material;
+        self
+    }
+}
+
+/// Watertight ray/triangle intersection (Woop, Benthin and Wald 2013), returns the ray
+/// parameter and the barycentric weights of the vertices a, b and c.
+///
+/// Möller–Trumbore loses rays through the edge shared by two triangles to rounding.
+/// Here the vertices are moved into a space where the ray runs along +Z from the
+/// origin, and the 2D edge functions of a shared edge are computed from the same
+/// two numbers in both triangles, so one of them always reports the hit.
+pub(crate) fn intersect(
+    ray: &Ray,
+    vertices: &[Point3; 3],
+    ray_t_range: Interval,
+) -> Option<(Double, [Double; 3])> {
+    let direction = ray.direction;
+    // the axis along which the direction is largest becomes z
+    let kz = (0..3)
+        .max_by(|&i, &j| direction[i].abs().total_cmp(&direction[j].abs()))
+        .unwrap_or(2);
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    // swap x and y to preserve the winding direction of the triangle
+    if direction[kz] < 0.0 {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+    // shear constants
+    let sx = direction[kx] / direction[kz];
+    let sy = direction[ky] / direction[kz];
+    let sz = 1.0 / direction[kz];
+
+    // vertices relative to the ray origin, sheared onto the xy plane
+    let [a, b, c] = vertices.map(|p| {
+        let p = p - ray.origin;
+        [p[kx] - sx * p[kz], p[ky] - sy * p[kz], sz * p[kz]]
+    });
+    // scaled barycentric coordinates, the signed areas opposite each vertex
+    let u = c[0] * b[1] - c[1] * b[0];
+    let v = a[0] * c[1] - a[1] * c[0];
+    let w = b[0] * a[1] - b[1] * a[0];
+    if (u < 0.0 || v < 0.0 || w < 0.0) && (u > 0.0 || v > 0.0 || w > 0.0) {
+        return None;
+    }
+    let determinant = u + v + w;
+    if determinant == 0.0 {
+        // the ray runs along the plane of the triangle
+        return None;
+    }
+    let t = (u * a[2] + v * b[2] + w * c[2]) / determinant;
+    if !ray_t_range.surrounds(t) {
+        return None;
+    }
+    Some((t, [u / determinant, v / determinant, w / determinant]))
+}
+
+/// Fill in the barycentric coordinates, texture coordinates and the smooth normal.
+pub(crate) fn shade<'a>(
+    record: HitRecord<'a>,
+    barycentric: [Double; 3],
+    normals: Option<&[Vector3; 3]>,
+    uvs: Option<&[[Double; 2]; 3]>,
+) -> HitRecord<'a> {
+    let [w, u, v] = barycentric;
+    let [tex_u, tex_v] = match uvs {
+        Some([uv0, uv1, uv2]) => [0, 1].map(|n| w * uv0[n] + u * uv1[n] + v * uv2[n]),
+        None => [u, v],
+    };
+    let record = record.with_barycentric(barycentric).with_uv(tex_u, tex_v);
+    match normals {
+        Some([n0, n1, n2]) => {
+            let normal = *n0 * w + *n1 * u + *n2 * v;
+            if normal.near_zero() {
+                record
+            } else {
+                record.with_shading_normal(normal.unit_vector())
+            }
+        }
+        None => record,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    fn random_point(rng: &mut Rng, extent: Double) -> Point3 {
+        Point3::new([(); 3].map(|_| rng.random_range(-extent, extent)))
+    }
+
+    /// Fire rays from random origins at `target(rng)`: the closest hit among `triangles`
+    /// must always be found, by at least one of the triangles.
+    fn assert_watertight(triangles: &[[Point3; 3]], mut target: impl FnMut(&mut Rng) -> Point3) {
+        let mut rng = Rng::new(3);
+        let range = Interval::new(0.001, f64::INFINITY);
+        for _ in 0..20_000 {
+            let origin = random_point(&mut rng, 5.0);
+            let ray = Ray::new(origin, target(&mut rng) - origin);
+            let hits = triangles
+                .iter()
+                .filter(|vertices| intersect(&ray, vertices, range).is_some())
+                .count();
+            assert!(hits >= 1, "ray from {origin:?} slipped through");
+            // triangles sharing the edge or vertex report the same point
+            let closest = triangles
+                .iter()
+                .filter_map(|vertices| intersect(&ray, vertices, range))
+                .map(|(t, _)| t)
+                .fold(f64::INFINITY, f64::min);
+            assert!((closest - 1.0).abs() < 1e-9, "hit at t = {closest}");
+        }
+    }
+
+    #[test]
+    fn shared_edge_is_watertight() {
+        let p = Point3::new([0.1, 0.2, 0.3]);
+        let q = Point3::new([0.7, 0.9, -0.4]);
+        // coplanar, on either side of the edge, so it is never a silhouette
+        let side = Vector3::new([0.5, -0.3, 0.8]);
+        let triangles = [
+            [p, q, p + (q - p) * 0.3 + side],
+            [q, p, p + ((q - p) * 0.6 - side)],
+        ];
+        assert_watertight(&triangles, |rng| {
+            let s = rng.random_double();
+            p + (q - p) * s
+        });
+    }
+
+    #[test]
+    fn shared_vertex_is_watertight() {
+        let center = Point3::new([0.3, -0.2, 0.1]);
+        let (u, v) = (
+            Vector3::new([0.9, 0.1, -0.3]),
+            Vector3::new([0.2, 0.8, 0.5]),
+        );
+        let count = 7;
+        let ring: Vec<Point3> = (0..count)
+            .map(|i| {
+                let angle = 2.0 * std::f64::consts::PI * i as Double / count as Double;
+                center + u * angle.cos() + v * angle.sin()
+            })
+            .collect();
+        let triangles: Vec<[Point3; 3]> = (0..count)
+            .map(|i| [center, ring[i], ring[(i + 1) % count]])
+            .collect();
+        assert_watertight(&triangles, |_| center);
+    }
+
+    #[test]
+    fn closest_hit_of_adjacent_triangles_is_one_record() {
+        let (a, b) = (Point3::new([0.0, 0.0, 0.0]), Point3::new([1.0, 0.0, 0.0]));
+        let (c, d) = (Point3::new([0.0, 1.0, 0.0]), Point3::new([1.0, 1.0, 0.0]));
+        let mut list = crate::hittable::HittableList::new();
+        list.push(Triangle::new(a, b, c));
+        list.push(Triangle::new(b, d, c));
+        let range = Interval::new(0.001, f64::INFINITY);
+        // on the diagonal, at its ends and through the shared vertices
+        for target in [[0.5, 0.5], [0.25, 0.75], [1.0, 0.0], [0.0, 1.0]] {
+            let origin = Point3::new([0.2, 0.4, 2.0]);
+            let point = Point3::new([target[0], target[1], 0.0]);
+            let record = list.hit(&Ray::new(origin, point - origin), range);
+            let record = record.expect("ray through the shared edge missed");
+            assert!((record.ray_t - 1.0).abs() < 1e-12);
+        }
+    }
+}
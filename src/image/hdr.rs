@@ -0,0 +1,363 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::color::RGB;
+
+use super::Image;
+
+/// Write `image` as a Radiance `.hdr` file.
+pub fn write(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode(image))
+}
+
+/// Encode `image` as run length encoded RGBE (Radiance HDR).
+pub fn encode(image: &Image) -> Vec<u8> {
+    let (width, height) = (image.width(), image.height());
+    let mut buf = Vec::new();
+    buf.extend_from_slice(b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n");
+    buf.extend_from_slice(format!("-Y {} +X {}\n", height, width).as_bytes());
+    for row in image.rows() {
+        let rgbe: Vec<[u8; 4]> = row.iter().map(to_rgbe).collect();
+        // the run length encoding only applies to scanlines of this width
+        if !(8..0x8000).contains(&width) {
+            rgbe.iter().for_each(|pixel| buf.extend_from_slice(pixel));
+            continue;
+        }
+        buf.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+        // each of the four components is encoded separately
+        for component in 0..4 {
+            let data: Vec<u8> = rgbe.iter().map(|pixel| pixel[component]).collect();
+            encode_runs(&data, &mut buf);
+        }
+    }
+    buf
+}
+
+/// Read a Radiance `.hdr` file.
+pub fn read(path: impl AsRef<Path>) -> io::Result<Image> {
+    decode(&fs::read(path)?)
+}
+
+/// Decode a Radiance HDR image in the standard `-Y height +X width` orientation,
+/// with flat or run length encoded scanlines.
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut lines = HeaderLines { data, offset: 0 };
+    let magic = lines.next().ok_or_else(|| invalid("empty file"))?;
+    if !magic.starts_with(b"#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+    // variables until the empty line, then the resolution
+    loop {
+        let line = lines.next().ok_or_else(|| invalid("missing resolution"))?;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with(b"FORMAT=") && line != b"FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid("unsupported pixel format"));
+        }
+    }
+    let resolution = lines.next().ok_or_else(|| invalid("missing resolution"))?;
+    let resolution = std::str::from_utf8(resolution).map_err(|_| invalid("invalid resolution"))?;
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse::<u32>(), width.parse::<u32>()),
+        _ => return Err(invalid("unsupported orientation")),
+    };
+    let (Ok(height), Ok(width)) = (height, width) else {
+        return Err(invalid("invalid resolution"));
+    };
+
+    let mut reader = ScanlineReader {
+        data: &data[lines.offset..],
+        position: 0,
+    };
+    // reject sizes the data cannot hold before allocating for them
+    let pixel_count = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| invalid("image too large"))?;
+    let fits = min_scanline_length(width)
+        .checked_mul(height as usize)
+        .is_some_and(|length| length <= reader.data.len());
+    if !fits {
+        return Err(invalid("truncated pixel data"));
+    }
+    let mut pixels = Vec::with_capacity(pixel_count);
+    let mut scanline = vec![[0u8; 4]; width as usize];
+    for _ in 0..height {
+        reader
+            .read(&mut scanline)
+            .ok_or_else(|| invalid("truncated pixel data"))?;
+        pixels.extend(scanline.iter().map(from_rgbe));
+    }
+    Ok(Image::from_pixels(width, height, pixels))
+}
+
+/// Fewest bytes a scanline of `width` pixels can take, with the longest runs.
+fn min_scanline_length(width: u32) -> usize {
+    let width = width as usize;
+    if (8..0x8000).contains(&width) {
+        // marker, then one run of up to 127 bytes per two bytes, for each component
+        4 + 4 * 2 * width.div_ceil(127)
+    } else {
+        4 * width
+    }
+}
+
+struct HeaderLines<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for HeaderLines<'a> {
+    type Item = &'a [u8];
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.data[self.offset..];
+        let end = rest.iter().position(|&byte| byte == b'\n')?;
+        self.offset += end + 1;
+        Some(&rest[..end])
+    }
+}
+
+struct ScanlineReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl ScanlineReader<'_> {
+    fn take(&mut self, count: usize) -> Option<&[u8]> {
+        let bytes = self.data.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(bytes)
+    }
+    /// Fill `scanline`, `None` if the data ends early or is malformed.
+    fn read(&mut self, scanline: &mut [[u8; 4]]) -> Option<()> {
+        let width = scanline.len();
+        let start = self.data.get(self.position..self.position + 4)?;
+        let run_length_encoded = (8..0x8000).contains(&width) && start[0] == 2 && start[1] == 2;
+        if !run_length_encoded {
+            for pixel in scanline.iter_mut() {
+                pixel.copy_from_slice(self.take(4)?);
+            }
+            return Some(());
+        }
+        if ((start[2] as usize) << 8 | start[3] as usize) != width {
+            return None;
+        }
+        self.position += 4;
+        // the four components follow each other, see encode_runs
+        for component in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.take(1)?[0] as usize;
+                if count > 128 {
+                    let count = count - 128;
+                    let value = self.take(1)?[0];
+                    scanline
+                        .get_mut(x..x + count)?
+                        .iter_mut()
+                        .for_each(|pixel| pixel[component] = value);
+                    x += count;
+                } else {
+                    if count == 0 {
+                        return None;
+                    }
+                    let values = self.take(count)?.to_vec();
+                    for (pixel, value) in scanline.get_mut(x..x + count)?.iter_mut().zip(values) {
+                        pixel[component] = value;
+                    }
+                    x += count;
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+/// Inverse of [`to_rgbe`], taking the middle of the range each byte stands for.
+fn from_rgbe(rgbe: &[u8; 4]) -> RGB {
+    if rgbe[3] == 0 {
+        return RGB::default();
+    }
+    let scale = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+    RGB::new([0, 1, 2].map(|n| (rgbe[n] as f64 + 0.5) * scale))
+}
+
+/// Shared exponent encoding: the mantissas of the three channels are
+/// stored as bytes relative to the exponent of the brightest one.
+fn to_rgbe(color: &RGB) -> [u8; 4] {
+    let v = color.r().max(color.g()).max(color.b());
+    if v < 1e-32 {
+        return [0; 4];
+    }
+    // v = m * 2^e with m in [0.5, 1)
+    let exponent = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+    let [r, g, b] = color.0.map(|n| (n.max(0.0) * scale).min(255.0) as u8);
+    [r, g, b, (exponent + 128).clamp(0, 255) as u8]
+}
+
+/// A byte above 128 is followed by one value repeated `byte - 128` times,
+/// otherwise the byte counts the literal values which follow it.
+fn encode_runs(data: &[u8], buf: &mut Vec<u8>) {
+    const MIN_RUN_LENGTH: usize = 4;
+    let mut current = 0;
+    while current < data.len() {
+        // find the next run which is long enough to pay off
+        let mut begin_run = current;
+        let mut run_count = 0;
+        let mut old_run_count = 0;
+        while run_count < MIN_RUN_LENGTH && begin_run < data.len() {
+            begin_run += run_count;
+            old_run_count = run_count;
+            run_count = 1;
+            while begin_run + run_count < data.len()
+                && run_count < 127
+                && data[begin_run] == data[begin_run + run_count]
+            {
+                run_count += 1;
+            }
+        }
+        // a short run right at the start is cheaper as a run than as literals
+        if old_run_count > 1 && old_run_count == begin_run - current {
+            buf.extend_from_slice(&[128 + old_run_count as u8, data[current]]);
+            current = begin_run;
+        }
+        while current < begin_run {
+            let literal_count = (begin_run - current).min(128);
+            buf.push(literal_count as u8);
+            buf.extend_from_slice(&data[current..current + literal_count]);
+            current += literal_count;
+        }
+        if run_count >= MIN_RUN_LENGTH {
+            buf.extend_from_slice(&[128 + run_count as u8, data[begin_run]]);
+            current += run_count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One scanline with long runs, short runs and literals in every component.
+    fn image() -> Image {
+        let width = 40;
+        let pixels = (0..width)
+            .map(|x| match x {
+                0..10 => RGB::new([0.5, 0.25, 1.0]),
+                10..12 => RGB::new([2.0, 0.0, 0.0]),
+                12..20 => RGB::new([x as f64 / 20.0, 0.1, 0.001 * x as f64]),
+                _ => RGB::new([100.0, 3.0, 0.0]),
+            })
+            .collect();
+        Image::from_pixels(width, 1, pixels)
+    }
+
+    #[test]
+    fn scanlines_are_run_length_encoded() {
+        let image = image();
+        let data = encode(&image);
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 40\n";
+        assert_eq!(&data[..header.len()], header);
+        let body = &data[header.len()..];
+        // new style run length encoding marker with the width
+        assert_eq!(body[..4], [2, 2, 0, 40]);
+        // runs make it shorter than the flat scanline
+        assert!(body.len() < 4 + 40 * 4);
+
+        let mut reader = ScanlineReader {
+            data: body,
+            position: 0,
+        };
+        let mut scanline = vec![[0u8; 4]; 40];
+        reader.read(&mut scanline).unwrap();
+        assert_eq!(reader.position, body.len());
+        let expected: Vec<[u8; 4]> = image.pixels().iter().map(to_rgbe).collect();
+        assert_eq!(scanline, expected);
+    }
+
+    #[test]
+    fn runs_decode_to_the_input() {
+        let data: Vec<u8> = [vec![1; 3], vec![2; 200], (0..150).collect(), vec![9; 5]].concat();
+        let mut encoded = Vec::new();
+        encode_runs(&data, &mut encoded);
+        // the run of 200 takes two run codes
+        assert!(encoded.len() < data.len() / 2 + 10);
+        let mut decoded = Vec::new();
+        let mut position = 0;
+        while position < encoded.len() {
+            let count = encoded[position] as usize;
+            if count > 128 {
+                decoded.extend(std::iter::repeat_n(encoded[position + 1], count - 128));
+                position += 2;
+            } else {
+                assert!(count > 0);
+                decoded.extend_from_slice(&encoded[position + 1..position + 1 + count]);
+                position += 1 + count;
+            }
+        }
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn rgbe_keeps_eight_bits_of_mantissa() {
+        for value in [1e-3, 0.18, 1.0, 3.5, 1e4] {
+            let color = RGB::new([value, value * 0.5, 0.0]);
+            let decoded = from_rgbe(&to_rgbe(&color));
+            assert!((decoded.r() - value).abs() <= value / 128.0, "{value}");
+            assert!(
+                (decoded.g() - value * 0.5).abs() <= value / 128.0,
+                "{value}"
+            );
+        }
+        assert_eq!(to_rgbe(&RGB::default()), [0; 4]);
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        // wide enough for run length encoding, and too narrow for it
+        for width in [40, 5] {
+            let pixels = (0..width * 3)
+                .map(|i| RGB::new([i as f64 * 0.1, 1.0, 250.0 / (i + 1) as f64]))
+                .collect();
+            let image = Image::from_pixels(width, 3, pixels);
+            let path = std::env::temp_dir()
+                .join(format!("hdr-round-trip-{}-{width}.hdr", std::process::id()));
+            write(&image, &path).unwrap();
+            let read_back = read(&path);
+            fs::remove_file(&path).unwrap();
+            let read_back = read_back.unwrap();
+            assert_eq!((read_back.width(), read_back.height()), (width, 3));
+            for (actual, expected) in read_back.pixels().iter().zip(image.pixels()) {
+                let tolerance = expected.r().max(expected.g()).max(expected.b()) / 128.0;
+                for (a, e) in actual.0.iter().zip(expected.0) {
+                    assert!((a - e).abs() <= tolerance, "{actual:?} for {expected:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let data = encode(&image());
+        for length in [0, 10, 47, data.len() - 1] {
+            let error = decode(&data[..length]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn huge_header_is_an_error() {
+        for resolution in [
+            "-Y 4294967295 +X 4294967295",
+            "-Y 1 +X 4294967295",
+            "-Y 4294967295 +X 100",
+        ] {
+            let mut data = format!("#?RADIANCE\n\n{resolution}\n").into_bytes();
+            data.extend_from_slice(&[2, 2, 0, 100, 255, 1, 255, 1]);
+            let error = decode(&data).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{resolution}");
+        }
+    }
+}
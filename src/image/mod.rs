@@ -0,0 +1,178 @@
+use std::io;
+use std::path::Path;
+use std::slice::Chunks;
+
+use crate::Double;
+use crate::color::{DisplayTransform, RGB, ToneMap, TransferFunction};
+pub mod hdr;
+pub mod pfm;
+pub mod png;
+pub mod ppm;
+mod zlib;
+
+/// Framebuffer of linear [`RGB`] values, stored row by row from the top left corner.
+///
+/// The pixels stay linear, `display` is applied by the 8 and 16 bit writers.
+#[derive(Clone, Debug)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<RGB>,
+    display: DisplayTransform,
+}
+
+impl Image {
+    /// A black image of the given size.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::from_pixels(
+            width,
+            height,
+            vec![RGB::default(); width as usize * height as usize],
+        )
+    }
+    /// # Panics
+    /// if `pixels` does not hold exactly `width * height` values.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<RGB>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        Self {
+            width,
+            height,
+            pixels,
+            display: Default::default(),
+        }
+    }
+    pub fn with_display(mut self, display: DisplayTransform) -> Self {
+        self.display = display;
+        self
+    }
+    pub fn with_transfer(mut self, transfer: TransferFunction) -> Self {
+        self.display.transfer = transfer;
+        self
+    }
+    pub fn with_tone_map(mut self, tone_map: ToneMap) -> Self {
+        self.display.tone_map = tone_map;
+        self
+    }
+    /// Exposure compensation in stops.
+    pub fn with_exposure(mut self, exposure: Double) -> Self {
+        self.display.exposure = exposure;
+        self
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn display(&self) -> DisplayTransform {
+        self.display
+    }
+    pub fn pixels(&self) -> &[RGB] {
+        &self.pixels
+    }
+    pub fn pixels_mut(&mut self) -> &mut [RGB] {
+        &mut self.pixels
+    }
+    /// # Panics
+    /// if the pixel `(x, y)` is outside the image.
+    pub fn get(&self, x: u32, y: u32) -> RGB {
+        self.pixels[self.index(x, y)]
+    }
+    /// # Panics
+    /// if the pixel `(x, y)` is outside the image.
+    pub fn set(&mut self, x: u32, y: u32, color: RGB) {
+        let index = self.index(x, y);
+        self.pixels[index] = color;
+    }
+    /// Iterate over the rows, top to bottom.
+    pub fn rows(&self) -> Chunks<'_, RGB> {
+        self.pixels.chunks(self.width.max(1) as usize)
+    }
+    /// Write the image in the format picked by the extension of `path`:
+    /// `.ppm` (binary P6), `.png` (8 bit), `.pfm` or `.hdr`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("ppm") => ppm::write_p6(self, path),
+            Some("png") => png::write(self, path),
+            Some("pfm") => pfm::write(self, path),
+            Some("hdr") => hdr::write(self, path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported image format: {}", path.display()),
+            )),
+        }
+    }
+    /// Read a high dynamic range image, `.hdr` or `.pfm` by the extension of `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("pfm") => pfm::read(path),
+            Some("hdr") => hdr::read(path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported image format: {}", path.display()),
+            )),
+        }
+    }
+    fn index(&self, x: u32, y: u32) -> usize {
+        // an x past the width would silently land on the next row
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) outside of a {}x{} image",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_returns_what_set_stored() {
+        let mut image = Image::new(3, 2);
+        assert!(image.pixels().iter().all(|pixel| pixel.0 == [0.0; 3]));
+        image.set(2, 0, RGB::new([1.0, 2.0, 3.0]));
+        image.set(0, 1, RGB::new([4.0, 5.0, 6.0]));
+        assert_eq!(image.get(2, 0).0, [1.0, 2.0, 3.0]);
+        assert_eq!(image.get(0, 1).0, [4.0, 5.0, 6.0]);
+        assert_eq!(image.get(1, 0).0, [0.0; 3]);
+        // row by row from the top left corner
+        assert_eq!(image.pixels()[2].0, [1.0, 2.0, 3.0]);
+        assert_eq!(image.pixels()[3].0, [4.0, 5.0, 6.0]);
+        let rows: Vec<_> = image.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0].0, [4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel (3, 0) outside of a 3x2 image")]
+    fn get_past_the_width_panics() {
+        Image::new(3, 2).get(3, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "pixel (0, 2) outside of a 3x2 image")]
+    fn set_past_the_height_panics() {
+        Image::new(3, 2).set(0, 2, RGB::default());
+    }
+
+    #[test]
+    #[should_panic]
+    fn pixels_must_match_the_size() {
+        Image::from_pixels(3, 2, vec![RGB::default(); 5]);
+    }
+}
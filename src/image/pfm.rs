@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::color::RGB;
+
+use super::Image;
+
+/// Write `image` as a Portable Float Map, keeping the full range of the linear colors.
+pub fn write(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode(image))
+}
+
+/// Encode `image` as a color (`PF`) Portable Float Map.
+///
+/// A negative scale marks the samples as little endian `f32`,
+/// rows are stored from the bottom of the image to the top.
+pub fn encode(image: &Image) -> Vec<u8> {
+    let header = format!("PF\n{} {}\n-1.0\n", image.width(), image.height());
+    let mut buf = Vec::with_capacity(header.len() + image.pixels().len() * 12);
+    buf.extend_from_slice(header.as_bytes());
+    for row in image.rows().rev() {
+        for pixel_color in row {
+            for n in pixel_color.0 {
+                buf.extend_from_slice(&(n as f32).to_le_bytes());
+            }
+        }
+    }
+    buf
+}
+
+/// Read a Portable Float Map.
+pub fn read(path: impl AsRef<Path>) -> io::Result<Image> {
+    decode(&fs::read(path)?)
+}
+
+/// Decode a color (`PF`) or greyscale (`Pf`) Portable Float Map.
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    // three whitespace separated header tokens, then a single whitespace before the samples
+    let mut tokens = Vec::with_capacity(4);
+    let mut position = 0;
+    while tokens.len() < 4 {
+        while data.get(position).is_some_and(u8::is_ascii_whitespace) {
+            position += 1;
+        }
+        let start = position;
+        while data
+            .get(position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid("truncated header"));
+        }
+        let token =
+            std::str::from_utf8(&data[start..position]).map_err(|_| invalid("invalid header"))?;
+        tokens.push(token);
+    }
+    position += 1;
+    let channels = match tokens[0] {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(invalid("not a Portable Float Map")),
+    };
+    let (Ok(width), Ok(height), Ok(scale)) = (
+        tokens[1].parse::<u32>(),
+        tokens[2].parse::<u32>(),
+        tokens[3].parse::<f64>(),
+    ) else {
+        return Err(invalid("invalid header"));
+    };
+    let little_endian = scale < 0.0;
+    // the header is not trusted until the data is known to be there
+    let row_length = (width as usize)
+        .checked_mul(channels * 4)
+        .ok_or_else(|| invalid("image too large"))?;
+    let end = row_length
+        .checked_mul(height as usize)
+        .and_then(|length| length.checked_add(position))
+        .ok_or_else(|| invalid("image too large"))?;
+    let samples = data
+        .get(position..end)
+        .ok_or_else(|| invalid("truncated pixel data"))?;
+    let mut pixels = Vec::with_capacity(samples.len() / (channels * 4));
+    // rows are stored bottom to top
+    for row in samples.chunks_exact(row_length.max(1)).rev() {
+        for pixel in row.chunks_exact(channels * 4) {
+            let values: Vec<f64> = pixel
+                .chunks_exact(4)
+                .map(|bytes| {
+                    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                    if little_endian {
+                        f32::from_le_bytes(bytes) as f64
+                    } else {
+                        f32::from_be_bytes(bytes) as f64
+                    }
+                })
+                .collect();
+            pixels.push(match values[..] {
+                [r, g, b] => RGB::new([r, g, b]),
+                _ => RGB::new([values[0]; 3]),
+            });
+        }
+    }
+    Ok(Image::from_pixels(width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> Image {
+        let (width, height) = (2, 3);
+        let pixels = (0..width * height)
+            .map(|i| RGB::new([i as f64, 0.5 * i as f64, -1.25 * i as f64]))
+            .collect();
+        Image::from_pixels(width, height, pixels)
+    }
+
+    #[test]
+    fn rows_are_stored_bottom_to_top() {
+        let image = image();
+        let data = encode(&image);
+        let header = b"PF\n2 3\n-1.0\n";
+        assert_eq!(&data[..header.len()], header);
+        let samples: Vec<f32> = data[header.len()..]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(samples.len(), 2 * 3 * 3);
+        // the first stored row is the bottom one, pixels 4 and 5
+        assert_eq!(samples[..6], [4.0, 2.0, -5.0, 5.0, 2.5, -6.25]);
+        assert_eq!(samples[12..], [0.0, 0.0, 0.0, 1.0, 0.5, -1.25]);
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let image = image();
+        let path = std::env::temp_dir().join(format!("pfm-round-trip-{}.pfm", std::process::id()));
+        write(&image, &path).unwrap();
+        let read_back = read(&path);
+        fs::remove_file(&path).unwrap();
+        let read_back = read_back.unwrap();
+        assert_eq!((read_back.width(), read_back.height()), (2, 3));
+        // every value is exact in f32
+        assert_eq!(read_back.pixels(), image.pixels());
+    }
+
+    #[test]
+    fn big_endian_greyscale_is_read() {
+        let mut data = b"Pf\n2 1\n1.0\n".to_vec();
+        data.extend_from_slice(&0.5f32.to_be_bytes());
+        data.extend_from_slice(&2.0f32.to_be_bytes());
+        let image = decode(&data).unwrap();
+        assert_eq!(image.pixels(), [RGB::new([0.5; 3]), RGB::new([2.0; 3])]);
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let data = encode(&image());
+        for length in [0, 3, 10, data.len() - 1] {
+            let error = decode(&data[..length]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn huge_header_is_an_error() {
+        for header in [
+            "PF\n4294967295 4294967295\n-1.0\n",
+            "PF\n4294967295 1\n-1.0\n",
+            "Pf\n1 4294967295\n-1.0\n",
+        ] {
+            let mut data = header.as_bytes().to_vec();
+            data.extend_from_slice(&[0; 64]);
+            let error = decode(&data).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{header:?}");
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{Image, zlib};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Bits per channel of the written file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// Write `image` as an 8 bit per channel PNG file.
+pub fn write(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    write_with_depth(image, path, BitDepth::Eight)
+}
+
+pub fn write_with_depth(image: &Image, path: impl AsRef<Path>, depth: BitDepth) -> io::Result<()> {
+    fs::write(path, encode(image, depth))
+}
+
+/// Encode `image` as a truecolor PNG.
+pub fn encode(image: &Image, depth: BitDepth) -> Vec<u8> {
+    let (width, height) = (image.width(), image.height());
+    let bytes_per_pixel = match depth {
+        BitDepth::Eight => 3,
+        BitDepth::Sixteen => 6,
+    };
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // bit depth, color type 2 (truecolor), compression, filter and interlace methods
+    ihdr.extend_from_slice(&[bytes_per_pixel as u8 / 3 * 8, 2, 0, 0, 0]);
+
+    let stride = width as usize * bytes_per_pixel;
+    let mut filtered = Vec::with_capacity((stride + 1) * height as usize);
+    let mut previous = vec![0u8; stride];
+    let mut current = Vec::with_capacity(stride);
+    for row in image.rows() {
+        current.clear();
+        for pixel_color in row {
+            match depth {
+                BitDepth::Eight => {
+                    current.extend_from_slice(&pixel_color.to_bytes(image.display()))
+                }
+                BitDepth::Sixteen => pixel_color
+                    .to_words(image.display())
+                    .iter()
+                    .for_each(|word| current.extend_from_slice(&word.to_be_bytes())),
+            }
+        }
+        filter_row(&current, &previous, bytes_per_pixel, &mut filtered);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let mut buf = SIGNATURE.to_vec();
+    write_chunk(&mut buf, b"IHDR", &ihdr);
+    write_chunk(&mut buf, b"IDAT", &zlib::compress(&filtered));
+    write_chunk(&mut buf, b"IEND", &[]);
+    buf
+}
+
+/// Append the scanline filtered with whichever of the five filter types gives the
+/// smallest sum of absolute values, a cheap estimate of how well it compresses.
+fn filter_row(row: &[u8], previous: &[u8], bytes_per_pixel: usize, out: &mut Vec<u8>) {
+    let mut best: Option<(u64, u8, Vec<u8>)> = None;
+    for filter_type in 0..5u8 {
+        let candidate: Vec<u8> = (0..row.len())
+            .map(|i| {
+                let left = if i >= bytes_per_pixel {
+                    row[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let up = previous[i];
+                let up_left = if i >= bytes_per_pixel {
+                    previous[i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let predictor = match filter_type {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    _ => paeth(left, up, up_left),
+                };
+                row[i].wrapping_sub(predictor)
+            })
+            .collect();
+        let score = candidate
+            .iter()
+            .map(|&b| (b as i8).unsigned_abs() as u64)
+            .sum();
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, ..)| score < *best_score)
+        {
+            best = Some((score, filter_type, candidate));
+        }
+    }
+    if let Some((_, filter_type, candidate)) = best {
+        out.push(filter_type);
+        out.extend_from_slice(&candidate);
+    }
+}
+
+/// Pick whichever neighbour is closest to `left + up - up_left`.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let p = left as i16 + up as i16 - up_left as i16;
+    let pa = (p - left as i16).abs();
+    let pb = (p - up as i16).abs();
+    let pc = (p - up_left as i16).abs();
+    if pa <= pb && pa <= pc {
+        left
+    } else if pb <= pc {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// Length, type, data and a CRC over type and data.
+fn write_chunk(buf: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = buf.len();
+    buf.extend_from_slice(chunk_type);
+    buf.extend_from_slice(data);
+    let crc = crc32(&buf[start..]);
+    buf.extend_from_slice(&crc.to_be_bytes());
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// CRC-32 as used by PNG (and zip/gzip).
+pub fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(0xffff_ffffu32, |c, &byte| {
+        CRC_TABLE[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8)
+    });
+    crc ^ 0xffff_ffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    #[test]
+    fn crc32_of_check_string() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    /// Split a PNG into its chunks, checking the signature and every CRC.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], &[u8])> {
+        assert_eq!(png[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut position = 8;
+        while position < png.len() {
+            let length = u32::from_be_bytes(png[position..position + 4].try_into().unwrap());
+            let body = &png[position + 4..position + 8 + length as usize];
+            let crc = &png[position + 8 + length as usize..position + 12 + length as usize];
+            assert_eq!(crc, crc32(body).to_be_bytes());
+            chunks.push((body[..4].try_into().unwrap(), &body[4..]));
+            position += 12 + length as usize;
+        }
+        chunks
+    }
+
+    /// Undo the per scanline filters.
+    fn unfilter(data: &[u8], stride: usize, bytes_per_pixel: usize) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        for line in data.chunks_exact(stride + 1) {
+            let start = out.len();
+            for (i, &value) in line[1..].iter().enumerate() {
+                let left = if i >= bytes_per_pixel {
+                    out[start + i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let up = if start > 0 {
+                    out[start - stride + i]
+                } else {
+                    0
+                };
+                let up_left = if start > 0 && i >= bytes_per_pixel {
+                    out[start - stride + i - bytes_per_pixel]
+                } else {
+                    0
+                };
+                let predictor = match line[0] {
+                    0 => 0,
+                    1 => left,
+                    2 => up,
+                    3 => ((left as u16 + up as u16) / 2) as u8,
+                    4 => paeth(left, up, up_left),
+                    filter_type => panic!("invalid filter type {filter_type}"),
+                };
+                out.push(value.wrapping_add(predictor));
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn encoded_pixels_decode_back() {
+        let (width, height) = (13, 7);
+        let mut image = Image::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = [x as f64 / width as f64, y as f64 / height as f64, 0.5];
+                image.set(x, y, RGB::new(color));
+            }
+        }
+        for depth in [BitDepth::Eight, BitDepth::Sixteen] {
+            let png = encode(&image, depth);
+            let chunks = chunks(&png);
+            let types: Vec<_> = chunks.iter().map(|(chunk_type, _)| chunk_type).collect();
+            assert_eq!(types, [b"IHDR", b"IDAT", b"IEND"]);
+            let ihdr = chunks[0].1;
+            assert_eq!(ihdr[..8], [0, 0, 0, 13, 0, 0, 0, 7]);
+            let bytes_per_pixel = match depth {
+                BitDepth::Eight => 3,
+                BitDepth::Sixteen => 6,
+            };
+            assert_eq!(ihdr[8..], [bytes_per_pixel as u8 / 3 * 8, 2, 0, 0, 0]);
+
+            let filtered = zlib::tests::decompress(chunks[1].1);
+            let samples = unfilter(&filtered, width as usize * bytes_per_pixel, bytes_per_pixel);
+            let expected: Vec<u8> = image
+                .pixels()
+                .iter()
+                .flat_map(|pixel| match depth {
+                    BitDepth::Eight => pixel.to_bytes(image.display()).to_vec(),
+                    BitDepth::Sixteen => pixel
+                        .to_words(image.display())
+                        .iter()
+                        .flat_map(|word| word.to_be_bytes())
+                        .collect(),
+                })
+                .collect();
+            assert_eq!(samples, expected, "{depth:?}");
+        }
+    }
+}
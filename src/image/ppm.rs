@@ -0,0 +1,79 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::Image;
+
+/// Write `image` as an ASCII (P3) PPM file.
+pub fn write_p3(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode_p3(image))
+}
+
+/// Encode `image` as an ASCII (P3) PPM.
+pub fn encode_p3(image: &Image) -> String {
+    let mut buf = String::new();
+    let _ = write!(buf, "P3\n{} {}\n255\n", image.width(), image.height());
+    for pixel_color in image.pixels() {
+        pixel_color.write_color(&mut buf, image.display());
+    }
+    buf
+}
+
+/// Write `image` as a binary (P6) PPM file.
+pub fn write_p6(image: &Image, path: impl AsRef<Path>) -> io::Result<()> {
+    fs::write(path, encode_p6(image))
+}
+
+/// Encode `image` as a binary (P6) PPM, three bytes per pixel.
+pub fn encode_p6(image: &Image) -> Vec<u8> {
+    let header = format!("P6\n{} {}\n255\n", image.width(), image.height());
+    let mut buf = Vec::with_capacity(header.len() + image.pixels().len() * 3);
+    buf.extend_from_slice(header.as_bytes());
+    for pixel_color in image.pixels() {
+        buf.extend_from_slice(&pixel_color.to_bytes(image.display()));
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    fn image() -> Image {
+        let mut image = Image::new(3, 2);
+        image.set(0, 0, RGB::new([1.0, 0.0, 0.0]));
+        image.set(2, 0, RGB::new([0.0, 0.0, 1.0]));
+        image.set(1, 1, RGB::new([0.18, 0.5, 2.0]));
+        image
+    }
+
+    #[test]
+    fn p6_has_header_then_three_bytes_per_pixel() {
+        let image = image();
+        let data = encode_p6(&image);
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&data[..header.len()], header);
+        let bytes = &data[header.len()..];
+        assert_eq!(bytes.len(), 3 * 2 * 3);
+        assert_eq!(bytes[..3], [255, 0, 0]);
+        assert_eq!(bytes[6..9], [0, 0, 255]);
+        for (pixel, expected) in bytes.chunks_exact(3).zip(image.pixels()) {
+            assert_eq!(pixel, expected.to_bytes(image.display()));
+        }
+    }
+
+    #[test]
+    fn p3_lists_the_same_values() {
+        let image = image();
+        let text = encode_p3(&image);
+        let mut tokens = text.split_whitespace();
+        assert_eq!(tokens.next(), Some("P3"));
+        assert_eq!(tokens.next(), Some("3"));
+        assert_eq!(tokens.next(), Some("2"));
+        assert_eq!(tokens.next(), Some("255"));
+        let values: Vec<u8> = tokens.map(|token| token.parse().unwrap()).collect();
+        assert_eq!(values, encode_p6(&image)[11..]);
+    }
+}
@@ -0,0 +1,352 @@
+//! Minimal zlib (RFC 1950) stream with a single fixed Huffman deflate block (RFC 1951).
+
+/// Largest distance a match may reach back
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+/// Base length and extra bits of the length codes 257..=285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+/// Base distance and extra bits of the distance codes 0..=29
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Compress `data` into a zlib stream.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    // CMF: deflate with a 32K window, FLG: default level, header is a multiple of 31
+    let mut out = vec![0x78, 0x9c];
+    let mut writer = BitWriter::new(out);
+    // BFINAL = 1, BTYPE = 01 (fixed Huffman codes)
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+    deflate_fixed(data, &mut writer);
+    write_literal_length(&mut writer, 256);
+    out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// Adler-32 checksum of the uncompressed data.
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    // 5552 is the largest run which cannot overflow the u32 sums
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
+
+/// Greedy LZ77 over hash chains of three byte prefixes.
+fn deflate_fixed(data: &[u8], writer: &mut BitWriter) {
+    let mut chains = HashChains::new(data);
+    let mut pos = 0;
+    while pos < data.len() {
+        let (best_len, best_dist) = chains.longest_match(pos);
+        if best_len >= MIN_MATCH {
+            write_match(writer, best_len, best_dist);
+            for p in pos..pos + best_len {
+                chains.insert(p);
+            }
+            pos += best_len;
+        } else {
+            write_literal_length(writer, data[pos] as u16);
+            chains.insert(pos);
+            pos += 1;
+        }
+    }
+}
+
+/// Earlier positions in the window, grouped by the hash of the three bytes starting there.
+struct HashChains<'a> {
+    data: &'a [u8],
+    /// Most recent position for each hash
+    head: Vec<usize>,
+    /// Previous position with the same hash, indexed by position modulo the window
+    prev: Vec<usize>,
+}
+
+impl<'a> HashChains<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            prev: vec![usize::MAX; WINDOW_SIZE],
+        }
+    }
+    fn hash(&self, pos: usize) -> usize {
+        let data = self.data;
+        let key = (data[pos] as u32) << 16 | (data[pos + 1] as u32) << 8 | data[pos + 2] as u32;
+        (key.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    }
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH <= self.data.len() {
+            let h = self.hash(pos);
+            self.prev[pos % WINDOW_SIZE] = self.head[h];
+            self.head[h] = pos;
+        }
+    }
+    /// Returns the length and distance of the longest match found for `pos`.
+    fn longest_match(&self, pos: usize) -> (usize, usize) {
+        let data = self.data;
+        let (mut best_len, mut best_dist) = (0, 0);
+        if pos + MIN_MATCH > data.len() {
+            return (best_len, best_dist);
+        }
+        let max_len = MAX_MATCH.min(data.len() - pos);
+        let mut candidate = self.head[self.hash(pos)];
+        let mut chain = 0;
+        while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+            let len = data[candidate..]
+                .iter()
+                .zip(&data[pos..pos + max_len])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if len > best_len {
+                (best_len, best_dist) = (len, pos - candidate);
+                if len == max_len {
+                    break;
+                }
+            }
+            let next = self.prev[candidate % WINDOW_SIZE];
+            // stale entries point forward once the window wrapped around
+            if next == usize::MAX || next >= candidate {
+                break;
+            }
+            candidate = next;
+            chain += 1;
+        }
+        (best_len, best_dist)
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let code = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+    write_literal_length(writer, 257 + code as u16);
+    writer.write_bits(
+        (length - LENGTH_BASE[code] as usize) as u32,
+        LENGTH_EXTRA[code] as u32,
+    );
+    let code = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+    // distance codes are plain 5 bit numbers
+    writer.write_code(code as u32, 5);
+    writer.write_bits(
+        (distance - DISTANCE_BASE[code] as usize) as u32,
+        DISTANCE_EXTRA[code] as u32,
+    );
+}
+
+/// The fixed literal/length code of RFC 1951, section 3.2.6.
+fn write_literal_length(writer: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+/// Packs bits starting at the least significant bit of each byte.
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        Self {
+            out,
+            buffer: 0,
+            count: 0,
+        }
+    }
+    fn write_bits(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+    /// Huffman codes are packed starting with their most significant bit.
+    fn write_code(&mut self, code: u32, bits: u32) {
+        self.write_bits(code.reverse_bits() >> (32 - bits), bits);
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Reads bits starting at the least significant bit of each byte.
+    struct BitReader<'a> {
+        data: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let byte = self.data[self.position / 8];
+            let bit = (byte >> (self.position % 8)) & 1;
+            self.position += 1;
+            bit as u32
+        }
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, i| value | self.bit() << i)
+        }
+        /// Huffman codes come most significant bit first.
+        fn code(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, _| value << 1 | self.bit())
+        }
+        fn align(&mut self) {
+            self.position = self.position.div_ceil(8) * 8;
+        }
+    }
+
+    /// Inverse of `write_literal_length`.
+    fn read_literal_length(reader: &mut BitReader) -> u32 {
+        let code = reader.code(7);
+        if code <= 0x17 {
+            return 256 + code;
+        }
+        let code = code << 1 | reader.bit();
+        match code {
+            0x30..=0xbf => code - 0x30,
+            0xc0..=0xc7 => 280 + code - 0xc0,
+            _ => 144 + (code << 1 | reader.bit()) - 0x190,
+        }
+    }
+
+    /// Decompress a zlib stream made of stored and fixed Huffman blocks,
+    /// checking the header and the Adler-32 trailer.
+    pub(in crate::image) fn decompress(stream: &[u8]) -> Vec<u8> {
+        let (cmf, flg) = (stream[0], stream[1]);
+        assert_eq!(cmf & 0x0f, 8, "not deflate");
+        assert_eq!((cmf as u16 * 256 + flg as u16) % 31, 0, "bad header check");
+        let mut reader = BitReader {
+            data: &stream[..stream.len() - 4],
+            position: 16,
+        };
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let last = reader.bit() == 1;
+            match reader.bits(2) {
+                0 => {
+                    reader.align();
+                    let start = reader.position / 8;
+                    let length = u16::from_le_bytes([stream[start], stream[start + 1]]);
+                    let complement = u16::from_le_bytes([stream[start + 2], stream[start + 3]]);
+                    assert_eq!(length, !complement);
+                    out.extend_from_slice(&stream[start + 4..start + 4 + length as usize]);
+                    reader.position = (start + 4 + length as usize) * 8;
+                }
+                1 => loop {
+                    let symbol = read_literal_length(&mut reader);
+                    match symbol {
+                        0..=255 => out.push(symbol as u8),
+                        256 => break,
+                        _ => {
+                            let code = (symbol - 257) as usize;
+                            let length = LENGTH_BASE[code] as usize
+                                + reader.bits(LENGTH_EXTRA[code] as u32) as usize;
+                            let code = reader.code(5) as usize;
+                            let distance = DISTANCE_BASE[code] as usize
+                                + reader.bits(DISTANCE_EXTRA[code] as u32) as usize;
+                            for _ in 0..length {
+                                out.push(out[out.len() - distance]);
+                            }
+                        }
+                    }
+                },
+                block_type => panic!("unsupported block type {block_type}"),
+            }
+            if last {
+                break;
+            }
+        }
+        let trailer = &stream[stream.len() - 4..];
+        assert_eq!(trailer, adler32(&out).to_be_bytes(), "bad Adler-32");
+        out
+    }
+
+    #[test]
+    fn adler32_of_known_strings() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        // long enough to need the deferred modulo
+        let data = vec![0xff; 100_000];
+        let (a, b) = data.iter().fold((1u64, 0u64), |(a, b), &byte| {
+            let a = (a + byte as u64) % 65521;
+            (a, (b + a) % 65521)
+        });
+        assert_eq!(adler32(&data), (b << 16 | a) as u32);
+    }
+
+    #[test]
+    fn decompress_reads_a_stored_block() {
+        // zlib header, final stored block of 5 bytes, Adler-32 of "hello"
+        let mut stream = vec![0x78, 0x01, 0x01, 5, 0, !5, !0];
+        stream.extend_from_slice(b"hello");
+        stream.extend_from_slice(&adler32(b"hello").to_be_bytes());
+        assert_eq!(decompress(&stream), b"hello");
+    }
+
+    #[test]
+    fn compress_round_trips() {
+        let mut random = Vec::new();
+        let mut state = 1u32;
+        for _ in 0..70_000 {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            random.push((state >> 24) as u8);
+        }
+        let text = b"the quick brown fox jumps over the lazy dog. ".repeat(2000);
+        // a run longer than the window, matches must not reach past it
+        let mut long_runs = vec![7u8; 40_000];
+        long_runs.extend_from_slice(&random[..1000]);
+        long_runs.extend(vec![7u8; 40_000]);
+        let inputs: [&[u8]; 7] = [
+            b"",
+            b"a",
+            b"abcabcabcabcabcabc",
+            &[0; 1000],
+            &text,
+            &random,
+            &long_runs,
+        ];
+        for input in inputs {
+            let compressed = compress(input);
+            assert_eq!(decompress(&compressed), input, "{} bytes", input.len());
+        }
+        // the repetitive text must actually shrink
+        assert!(compress(&text).len() < text.len() / 10);
+    }
+}
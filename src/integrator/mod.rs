@@ -0,0 +1,22 @@
+use crate::{
+    color::RGB, environment::Environment, hittable::Hittable, light::LightList, ray::Ray,
+    sampler::Sampler,
+};
+pub mod path;
+
+/// Computes the radiance carried back along a camera ray.
+pub trait Integrator: Send + Sync {
+    fn ray_color(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> RGB;
+}
+
+/// What an integrator sees of the scene being rendered.
+#[derive(Clone, Copy)]
+pub struct Scene<'a> {
+    pub world: &'a dyn Hittable,
+    /// Emitters to sample directly, they must be part of `world` too
+    pub lights: &'a dyn Hittable,
+    /// Point, spot and directional lights, which have no geometry in `world`
+    pub light_list: &'a LightList,
+    /// Radiance of the rays which escape the scene
+    pub background: &'a dyn Environment,
+}
@@ -0,0 +1,396 @@
+use crate::{
+    Double,
+    color::RGB,
+    hittable::{HitRecord, SHADOW_ACNE_EPSILON},
+    interval::Interval,
+    ray::Ray,
+    sampler::Sampler,
+};
+
+use super::{Integrator, Scene};
+
+/// Unidirectional path tracer.
+///
+/// Diffuse bounces also sample the lights of the [`Scene`] directly, the two
+/// strategies are combined by multiple importance sampling.
+pub struct PathTracer {
+    /// Maximum number of ray bounces into scene
+    pub max_depth: u32,
+    /// Bounces after which paths are terminated by Russian roulette
+    pub roulette_depth: u32,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self {
+            max_depth: 50,
+            roulette_depth: 3,
+        }
+    }
+}
+
+impl PathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            ..Default::default()
+        }
+    }
+    pub fn with_roulette_depth(mut self, roulette_depth: u32) -> Self {
+        self.roulette_depth = roulette_depth;
+        self
+    }
+    /// `bsdf_pdf` is the density with which the previous bounce chose `ray`, `None` for
+    /// camera rays and specular bounces, whose emitters light sampling cannot find.
+    fn trace(
+        &self,
+        ray: &Ray,
+        scene: &Scene,
+        depth: u32,
+        bsdf_pdf: Option<Double>,
+        sampler: &mut dyn Sampler,
+    ) -> RGB {
+        // If we've exceeded the ray bounce limit, no more light is gathered.
+        if depth >= self.max_depth {
+            return RGB::default();
+        }
+        let Some(record) = scene
+            .world
+            .hit(ray, Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY))
+        else {
+            let direction = ray.direction.unit_vector();
+            let radiance = scene.background.radiance(direction);
+            // the previous bounce sampled the environment too
+            return match bsdf_pdf {
+                Some(bsdf_pdf) => {
+                    radiance * power_heuristic(bsdf_pdf, scene.background.pdf(direction))
+                }
+                None => radiance,
+            };
+        };
+        let emitted = record.material.emitted(ray, &record);
+        // the previous bounce sampled the lights too, weigh the two strategies
+        let emitted = match bsdf_pdf {
+            Some(bsdf_pdf) => {
+                let light_pdf = scene.lights.pdf_value(ray.origin, ray.direction);
+                emitted * power_heuristic(bsdf_pdf, light_pdf)
+            }
+            None => emitted,
+        };
+        let Some((mut attenuation, scattered)) = record.material.scatter(ray, &record, sampler)
+        else {
+            return emitted;
+        };
+        let scattering_pdf = record.material.scattering_pdf(ray, &record, &scattered);
+        let specular = scattering_pdf <= 0.0;
+        let direct = if specular {
+            RGB::default()
+        } else {
+            Self::sample_lights(ray, &record, scene, sampler)
+                + Self::sample_light_list(ray, &record, scene)
+                + Self::sample_environment(ray, &record, scene, sampler)
+        };
+        if depth >= self.roulette_depth {
+            // Russian roulette: survive with a probability following the
+            // attenuation, and boost the survivors to stay unbiased.
+            let survival = attenuation
+                .r()
+                .max(attenuation.g())
+                .max(attenuation.b())
+                .min(0.95);
+            if sampler.get_1d() >= survival {
+                return emitted + direct;
+            }
+            attenuation /= survival;
+        }
+        let bsdf_pdf = (!specular).then_some(scattering_pdf);
+        emitted + direct + attenuation * self.trace(&scattered, scene, depth + 1, bsdf_pdf, sampler)
+    }
+    /// Next-event estimation: the light reaching `record` along one direction
+    /// sampled towards the lights, unless the world blocks it.
+    fn sample_lights(
+        ray: &Ray,
+        record: &HitRecord,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+    ) -> RGB {
+        let direction = scene.lights.random(record.point, sampler);
+        let light_pdf = scene.lights.pdf_value(record.point, direction);
+        if light_pdf <= 0.0 {
+            return RGB::default();
+        }
+        let shadow_ray = Ray::new(record.point, direction);
+        let scattering = record.material.scattering(ray, record, &shadow_ray);
+        if scattering.r().max(scattering.g()).max(scattering.b()) <= 0.0 {
+            return RGB::default();
+        }
+        // the shadow ray gathers the emission of whatever it hits first
+        let Some(light) = scene.world.hit(
+            &shadow_ray,
+            Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY),
+        ) else {
+            return RGB::default();
+        };
+        let emitted = light.material.emitted(&shadow_ray, &light);
+        let bsdf_pdf = record.material.scattering_pdf(ray, record, &shadow_ray);
+        emitted * scattering * (power_heuristic(light_pdf, bsdf_pdf) / light_pdf)
+    }
+    /// Next-event estimation of the background: the radiance along one direction
+    /// sampled by the [`Environment`](crate::environment::Environment), unless the world blocks it.
+    fn sample_environment(
+        ray: &Ray,
+        record: &HitRecord,
+        scene: &Scene,
+        sampler: &mut dyn Sampler,
+    ) -> RGB {
+        let Some(direction) = scene.background.sample(sampler) else {
+            return RGB::default();
+        };
+        let environment_pdf = scene.background.pdf(direction);
+        if environment_pdf <= 0.0 {
+            return RGB::default();
+        }
+        let shadow_ray = Ray::new(record.point, direction);
+        let scattering = record.material.scattering(ray, record, &shadow_ray);
+        if scattering.r().max(scattering.g()).max(scattering.b()) <= 0.0 {
+            return RGB::default();
+        }
+        if scene
+            .world
+            .hit(
+                &shadow_ray,
+                Interval::new(SHADOW_ACNE_EPSILON, f64::INFINITY),
+            )
+            .is_some()
+        {
+            return RGB::default();
+        }
+        let radiance = scene.background.radiance(direction);
+        let bsdf_pdf = record.material.scattering_pdf(ray, record, &shadow_ray);
+        radiance * scattering * (power_heuristic(environment_pdf, bsdf_pdf) / environment_pdf)
+    }
+    /// Light reaching `record` from every light of the [`LightList`](crate::light::LightList).
+    ///
+    /// These lights are delta distributions, the BSDF can never find them, so no MIS weight applies.
+    fn sample_light_list(ray: &Ray, record: &HitRecord, scene: &Scene) -> RGB {
+        let mut radiance = RGB::default();
+        for light in scene.light_list.iter() {
+            let Some(sample) = light.sample_li(record.point) else {
+                continue;
+            };
+            let shadow_ray = Ray::new(record.point, sample.direction);
+            // only objects between the point and the light cast a shadow
+            let unoccluded =
+                Interval::new(SHADOW_ACNE_EPSILON, sample.distance - SHADOW_ACNE_EPSILON);
+            if scene.world.hit(&shadow_ray, unoccluded).is_some() {
+                continue;
+            }
+            // f * cos θ turns the irradiance at normal incidence into reflected radiance
+            radiance += record.material.scattering(ray, record, &shadow_ray) * sample.irradiance;
+        }
+        radiance
+    }
+}
+
+/// Multiple importance sampling weight of a sample taken with density `pdf`,
+/// when `other_pdf` is the density of the other strategy (Veach's power heuristic, β = 2).
+fn power_heuristic(pdf: Double, other_pdf: Double) -> Double {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b <= 0.0 { 0.0 } else { a / (a + b) }
+}
+
+impl Integrator for PathTracer {
+    fn ray_color(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> RGB {
+        self.trace(ray, scene, 0, None, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hittable::{HittableList, plane::Plane, quad::Quad},
+        light::{LightList, directional::DirectionalLight, point::PointLight},
+        material::{SharedMaterial, diffuse_light::DiffuseLight, lambertian::Lambertian},
+        sampler::independent::IndependentSampler,
+        vec3::{Point3, Vector3},
+    };
+
+    const ALBEDO: Double = 0.5;
+    const EMIT: Double = 4.0;
+
+    /// Unit square light facing down at height 1, centered over the origin.
+    fn light(material: &SharedMaterial) -> Quad {
+        Quad::new(
+            Point3::new([-0.5, 1.0, -0.5]),
+            Vector3::new([1.0, 0.0, 0.0]),
+            Vector3::new([0.0, 0.0, 1.0]),
+        )
+        .with_shared_material(material.clone())
+    }
+
+    /// Mean radiance leaving the floor at the origin towards a camera above it.
+    fn floor_radiance(integrator: &PathTracer, sample_lights: bool, samples: u64) -> Double {
+        let emitter: SharedMaterial = Arc::new(DiffuseLight::new(RGB::new([EMIT; 3])));
+        let mut world = HittableList::new();
+        world.push(
+            Plane::new(Point3::default(), Vector3::new([0.0, 1.0, 0.0]))
+                .with_material(Lambertian::new(RGB::new([ALBEDO; 3]))),
+        );
+        world.push(light(&emitter));
+        let mut lights = HittableList::new();
+        if sample_lights {
+            lights.push(light(&emitter));
+        }
+        let scene = Scene {
+            world: &world,
+            lights: &lights,
+            light_list: &LightList::new(),
+            background: &RGB::default(),
+        };
+        let ray = Ray::new(Point3::new([0.0, 0.5, 0.0]), Vector3::new([0.0, -1.0, 0.0]));
+        let mut sampler = IndependentSampler::new(7);
+        let sum: Double = (0..samples)
+            .map(|i| {
+                sampler.start_pixel_sample(0, i);
+                integrator.ray_color(&ray, &scene, &mut sampler).g()
+            })
+            .sum();
+        sum / samples as Double
+    }
+
+    /// Radiance reflected by the floor at the origin: albedo times the emitted radiance
+    /// times the form factor of the light, four squares with a corner above the point.
+    fn expected_radiance() -> Double {
+        let side: Double = 0.5;
+        let s = side / (1.0 + side * side).sqrt();
+        let corner = s * s.atan() / std::f64::consts::PI;
+        ALBEDO * EMIT * 4.0 * corner
+    }
+
+    #[test]
+    fn light_sampling_agrees_with_bsdf_sampling() {
+        let integrator = PathTracer::default();
+        let expected = expected_radiance();
+        let with_lights = floor_radiance(&integrator, true, 20_000);
+        let bsdf_only = floor_radiance(&integrator, false, 100_000);
+        assert!(
+            (with_lights - expected).abs() < 0.01 * expected,
+            "{with_lights} with light sampling, expected {expected}"
+        );
+        assert!(
+            (bsdf_only - expected).abs() < 0.03 * expected,
+            "{bsdf_only} with BSDF sampling, expected {expected}"
+        );
+    }
+
+    /// Radiance seen straight down onto a Lambertian floor under a white sky, `samples` times.
+    ///
+    /// Every bounce off the floor escapes, so each path which survives returns the albedo.
+    fn sky_lit_floor(integrator: &PathTracer, albedo: Double, samples: u64) -> Vec<Double> {
+        let mut world = HittableList::new();
+        world.push(
+            Plane::new(Point3::default(), Vector3::new([0.0, 1.0, 0.0]))
+                .with_material(Lambertian::new(RGB::new([albedo; 3]))),
+        );
+        let scene = Scene {
+            world: &world,
+            lights: &HittableList::new(),
+            light_list: &LightList::new(),
+            background: &RGB::new([1.0; 3]),
+        };
+        let ray = Ray::new(Point3::new([0.0, 1.0, 0.0]), Vector3::new([0.0, -1.0, 0.0]));
+        let mut sampler = IndependentSampler::new(5);
+        (0..samples)
+            .map(|i| {
+                sampler.start_pixel_sample(0, i);
+                integrator.ray_color(&ray, &scene, &mut sampler).g()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn depth_limit_cuts_the_path() {
+        // no bounce at all, not even the sky
+        assert_eq!(sky_lit_floor(&PathTracer::new(0), 0.5, 10), [0.0; 10]);
+        // the floor is hit, the bounce to the sky is past the limit
+        assert_eq!(sky_lit_floor(&PathTracer::new(1), 0.5, 10), [0.0; 10]);
+        let integrator = PathTracer::new(2).with_roulette_depth(2);
+        assert_eq!(sky_lit_floor(&integrator, 0.5, 10), [0.5; 10]);
+    }
+
+    #[test]
+    fn russian_roulette_stays_unbiased() {
+        // survival follows the albedo, capped at 0.95, and survivors are boosted by it
+        for (albedo, survival) in [(0.5, 0.5), (0.99, 0.95)] {
+            let integrator = PathTracer::default().with_roulette_depth(0);
+            let samples = sky_lit_floor(&integrator, albedo, 100_000);
+            let boosted = albedo / survival;
+            assert!(
+                samples
+                    .iter()
+                    .all(|&n| n == 0.0 || (n - boosted).abs() < 1e-12)
+            );
+            let survivors = samples.iter().filter(|&&n| n > 0.0).count() as Double;
+            assert!((survivors / samples.len() as Double - survival).abs() < 0.01);
+            let mean = samples.iter().sum::<Double>() / samples.len() as Double;
+            assert!((mean - albedo).abs() < 0.01 * albedo, "{mean} for {albedo}");
+        }
+    }
+
+    /// Radiance leaving a white Lambertian floor at the origin, lit by `light_list` only.
+    fn lit_floor(light_list: &LightList) -> Double {
+        let mut world = HittableList::new();
+        world.push(
+            Plane::new(Point3::default(), Vector3::new([0.0, 1.0, 0.0]))
+                .with_material(Lambertian::new(RGB::new([1.0; 3]))),
+        );
+        let scene = Scene {
+            world: &world,
+            lights: &HittableList::new(),
+            light_list,
+            background: &RGB::default(),
+        };
+        let ray = Ray::new(
+            Point3::new([0.0, 1.0, 1.0]),
+            Vector3::new([0.0, -1.0, -1.0]),
+        );
+        let mut sampler = IndependentSampler::new(3);
+        sampler.start_pixel_sample(0, 0);
+        PathTracer::default()
+            .ray_color(&ray, &scene, &mut sampler)
+            .g()
+    }
+
+    #[test]
+    fn delta_lights_follow_the_cosine_law() {
+        // radiance of a white Lambertian surface is E cos θ / π
+        let pi = std::f64::consts::PI;
+        let mut lights = LightList::new();
+        let (sin, cos) = 60.0_f64.to_radians().sin_cos();
+        lights.push(DirectionalLight::new(
+            Vector3::new([sin, -cos, 0.0]),
+            RGB::new([pi; 3]),
+        ));
+        assert!((lit_floor(&lights) - cos).abs() < 1e-12);
+
+        lights.clear();
+        lights.push(PointLight::new(
+            Point3::new([-2.0, 2.0, 0.0]),
+            RGB::new([8.0 * pi; 3]),
+        ));
+        // 8π / (√8)^2 at 45 degrees
+        let expected = 0.5_f64.sqrt();
+        assert!((lit_floor(&lights) - expected).abs() < 1e-12);
+
+        // the light is below the floor
+        lights.clear();
+        lights.push(PointLight::new(
+            Point3::new([0.0, -1.0, 0.0]),
+            RGB::new([1.0; 3]),
+        ));
+        assert_eq!(lit_floor(&lights), 0.0);
+    }
+}
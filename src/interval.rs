@@ -1,19 +1,57 @@
 use crate::Double;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Interval {
     pub min: Double,
     pub max: Double,
 }
 
+impl Default for Interval {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
 impl Interval {
+    pub const EMPTY: Interval = Interval {
+        min: f64::INFINITY,
+        max: f64::NEG_INFINITY,
+    };
+    pub const UNIVERSE: Interval = Interval {
+        min: f64::NEG_INFINITY,
+        max: f64::INFINITY,
+    };
     pub fn new(min: Double, max: Double) -> Self {
         Self { min, max }
     }
+    /// The tightest interval enclosing both `a` and `b`.
+    pub fn enclosing(a: Interval, b: Interval) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+    pub fn size(&self) -> Double {
+        self.max - self.min
+    }
     pub fn contains(&self, x: Double) -> bool {
         self.min <= x && x <= self.max
     }
     pub fn surrounds(&self, x: Double) -> bool {
         self.min < x && x < self.max
     }
+    pub fn clamp(&self, x: Double) -> Double {
+        if x < self.min {
+            self.min
+        } else if x > self.max {
+            self.max
+        } else {
+            x
+        }
+    }
+    /// Grow the interval by `delta`, half on each side.
+    pub fn expand(&self, delta: Double) -> Self {
+        let padding = delta / 2.0;
+        Self::new(self.min - padding, self.max + padding)
+    }
 }
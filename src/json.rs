@@ -0,0 +1,380 @@
+//! Minimal JSON reader, enough for the scene formats.
+
+use std::fmt;
+
+use crate::Double;
+
+/// A parsed JSON value, objects keep the order of their members.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(Double),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+static NULL: Value = Value::Null;
+
+impl Value {
+    /// Member `key` of an object, [`Value::Null`] if it is missing or this is not an object.
+    pub(crate) fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map_or(&NULL, |(_, value)| value),
+            _ => &NULL,
+        }
+    }
+    /// Element `index` of an array, [`Value::Null`] if it is out of range or this is not an array.
+    pub(crate) fn at(&self, index: usize) -> &Value {
+        match self {
+            Value::Array(elements) => elements.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+    pub(crate) fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+    pub(crate) fn as_f64(&self) -> Option<Double> {
+        match *self {
+            Value::Number(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// The number, if it is a non-negative integer.
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|value| *value >= 0.0 && value.fract() == 0.0)
+            .map(|value| value as usize)
+    }
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// Elements of an array, empty if this is not one.
+    pub(crate) fn elements(&self) -> &[Value] {
+        match self {
+            Value::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+    /// An array of `N` numbers.
+    pub(crate) fn as_numbers<const N: usize>(&self) -> Option<[Double; N]> {
+        let elements = self.elements();
+        if elements.len() != N {
+            return None;
+        }
+        let mut numbers = [0.0; N];
+        for (number, element) in numbers.iter_mut().zip(elements) {
+            *number = element.as_f64()?;
+        }
+        Some(numbers)
+    }
+}
+
+/// Malformed JSON, `line` counts from 1.
+#[derive(Debug)]
+pub(crate) struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse a complete JSON document.
+pub(crate) fn parse(source: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser {
+        bytes: source.as_bytes(),
+        position: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position < parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Nesting deeper than this is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let end = self.position.min(self.bytes.len());
+        ParseError {
+            line: 1 + self.bytes[..end].iter().filter(|&&b| b == b'\n').count(),
+            message: message.into(),
+        }
+    }
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", byte as char)))
+        }
+    }
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, ParseError> {
+        if self.bytes[self.position..].starts_with(word.as_bytes()) {
+            self.position += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+    fn value(&mut self, depth: usize) -> Result<Value, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+    fn object(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            members.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+    fn array(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Value::Array(elements));
+        }
+        loop {
+            elements.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Value::Array(elements));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.position;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        // the input is a str and the range only holds ascii
+        let text = std::str::from_utf8(&self.bytes[start..self.position]).unwrap_or_default();
+        text.parse()
+            .map(Value::Number)
+            .map_err(|_| self.error(format!("invalid number `{}`", text)))
+    }
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.position += 1;
+                    let ch = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        // escapes were encoded as utf-8 and the rest was copied from a str
+        String::from_utf8(bytes).map_err(|_| self.error("invalid utf-8"))
+    }
+    /// The 4 hex digits after `\u`, combining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !self.bytes[self.position..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate"));
+            }
+            self.position += 2;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid code point"))
+    }
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let digits = self
+            .bytes
+            .get(self.position..self.position + 4)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_line(source: &str) -> usize {
+        parse(source).expect_err(source).line
+    }
+
+    #[test]
+    fn values() {
+        let value =
+            parse(r#" {"b": [1, -2.5e1, true, null], "a": {"c": "d"}, "e": false} "#).unwrap();
+        let Value::Object(members) = &value else {
+            panic!("not an object");
+        };
+        let keys: Vec<_> = members.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["b", "a", "e"]);
+        assert_eq!(value.get("b").as_numbers::<2>(), None);
+        assert_eq!(value.get("b").at(1).as_f64(), Some(-25.0));
+        assert_eq!(value.get("b").at(0).as_usize(), Some(1));
+        assert_eq!(value.get("b").at(1).as_usize(), None);
+        assert_eq!(value.get("b").at(2).as_bool(), Some(true));
+        assert!(value.get("b").at(3).is_null());
+        assert!(value.get("b").at(4).is_null());
+        assert_eq!(value.get("a").get("c").as_str(), Some("d"));
+        assert_eq!(value.get("e").as_bool(), Some(false));
+        assert!(value.get("missing").get("c").is_null());
+        assert_eq!(parse("[]").unwrap(), Value::Array(Vec::new()));
+        assert_eq!(parse("{ }").unwrap(), Value::Object(Vec::new()));
+    }
+
+    #[test]
+    fn escapes() {
+        let value = parse(r#""\"\\\/\b\f\n\r\t\u00e9\u20AC""#).unwrap();
+        assert_eq!(value.as_str(), Some("\"\\/\u{8}\u{c}\n\r\t\u{e9}\u{20ac}"));
+        assert_eq!(parse("\"caf\u{e9}\"").unwrap().as_str(), Some("caf\u{e9}"));
+        for invalid in [
+            r#""\x""#,
+            r#""\u12""#,
+            r#""\u+123""#,
+            r#""\u00g0""#,
+            r#""abc"#,
+        ] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        let value = parse(r#""\ud83d\ude00 \uD834\uDD1E""#).unwrap();
+        assert_eq!(value.as_str(), Some("\u{1f600} \u{1d11e}"));
+        for unpaired in [
+            r#""\ud83d""#,
+            r#""\ud83d x""#,
+            r#""\ud83d\u0041""#,
+            r#""\ude00""#,
+        ] {
+            assert!(parse(unpaired).is_err(), "{unpaired}");
+        }
+    }
+
+    #[test]
+    fn depth_limit() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 2)).is_err());
+        // deep enough to overflow the stack without the limit
+        assert!(parse(&"{\"a\":".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn trailing_data() {
+        assert!(parse("1 \n\t").is_ok());
+        assert_eq!(error_line("{}\n}"), 2);
+        assert_eq!(error_line("1 2"), 1);
+        assert_eq!(error_line("[1,\n2]\n\nnull"), 4);
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        assert_eq!(error_line("{\n\"a\": 1,\n\"b\" 2\n}"), 3);
+        assert_eq!(error_line("[1,\n2,\n]"), 3);
+        assert_eq!(error_line("[1, tru]"), 1);
+        assert_eq!(error_line("\n\n"), 3);
+        assert_eq!(error_line("[1e]"), 1);
+        assert_eq!(error_line("{\"a\": [1, 2}"), 1);
+    }
+}
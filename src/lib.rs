@@ -1,18 +1,28 @@
 #![feature(macro_metavar_expr)]
 mod macros;
 //
+pub mod aabb;
+pub mod camera;
 pub mod color;
+pub mod environment;
+pub mod filter;
 pub mod hittable;
-pub mod camera;
+pub mod image;
+pub mod integrator;
 pub mod interval;
+mod json;
+pub mod light;
+pub mod material;
+pub mod random;
 pub mod ray;
+pub mod sampler;
 pub mod vec3;
 pub type Array3 = [f64; 3];
 pub type Double = f64;
 
 pub trait AsDouble {
+    #[allow(clippy::wrong_self_convention)]
     fn as_double(self) -> Double;
-    
 }
 macro_rules! as_double_impl {
     ($($type:ty),+) => {
@@ -24,4 +34,4 @@ macro_rules! as_double_impl {
         })+
     };
 }
-as_double_impl!{u8,u32,u64,i32,i64}
+as_double_impl! {u8,u32,u64,i32,i64}
@@ -0,0 +1,49 @@
+use crate::{
+    color::RGB,
+    vec3::{Point3, Vector3},
+};
+
+use super::{Light, LightSample};
+
+/// Light arriving from infinitely far away along one direction, like sunlight.
+pub struct DirectionalLight {
+    /// Unit vector the light travels along, from the light into the scene
+    pub direction: Vector3,
+    /// Irradiance on a surface facing the light
+    pub irradiance: RGB,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vector3, irradiance: RGB) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_li(&self, _point: Point3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            irradiance: self.irradiance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_irradiance_everywhere() {
+        let light = DirectionalLight::new(Vector3::new([0.0, -2.0, 0.0]), RGB::new([3.0; 3]));
+        for point in [[0.0; 3], [100.0, -5.0, 1e6]] {
+            let sample = light.sample_li(Point3::new(point)).unwrap();
+            assert_eq!(sample.direction.0, [0.0, 1.0, 0.0]);
+            assert_eq!(sample.distance, f64::INFINITY);
+            assert_eq!(sample.irradiance.0, [3.0; 3]);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{
+    Double,
+    color::RGB,
+    vec3::{Point3, Vector3},
+};
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+pub type LightBox = Box<dyn Light + 'static>;
+
+/// Light without a surface, it cannot be hit by rays and is only found by sampling it.
+pub trait Light: Send + Sync {
+    /// Light arriving at `point`, or `None` if none reaches it.
+    fn sample_li(&self, point: Point3) -> Option<LightSample>;
+}
+
+/// Light arriving at a point from one [`Light`].
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    /// Unit vector from the point towards the light
+    pub direction: Vector3,
+    /// Distance to the light, infinite for directional lights
+    pub distance: Double,
+    /// Irradiance on a surface facing the light
+    pub irradiance: RGB,
+}
+
+/// Inverse-square falloff, faded to zero at `range` as `KHR_lights_punctual` recommends.
+fn distance_falloff(distance_squared: Double, range: Option<Double>) -> Double {
+    match range {
+        Some(range) => {
+            // 1 - (d/r)^4, clamped
+            let ratio = distance_squared / (range * range);
+            (1.0 - ratio * ratio).clamp(0.0, 1.0) / distance_squared
+        }
+        None => 1.0 / distance_squared,
+    }
+}
+
+impl<L: Light + 'static> From<L> for LightBox {
+    fn from(value: L) -> Self {
+        Box::new(value) as LightBox
+    }
+}
+
+/// Lights of a scene, kept apart from the geometry in a [`HittableList`](crate::hittable::HittableList).
+#[derive(Default)]
+pub struct LightList {
+    pub lights: Vec<LightBox>,
+}
+
+impl From<Vec<LightBox>> for LightList {
+    fn from(lights: Vec<LightBox>) -> Self {
+        Self { lights }
+    }
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push<L: Light + 'static>(&mut self, light: L) {
+        self.lights.push(light.into());
+    }
+    pub fn clear(&mut self) {
+        self.lights.clear();
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Light> {
+        self.lights.iter().map(|light| light.as_ref())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Double, color::RGB, vec3::Point3};
+
+use super::{Light, LightSample, distance_falloff};
+
+/// Light shining equally in every direction from one point.
+pub struct PointLight {
+    pub position: Point3,
+    /// Radiant intensity, power per solid angle
+    pub intensity: RGB,
+    /// Distance at which the light has faded out, unlimited if `None`
+    pub range: Option<Double>,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: RGB) -> Self {
+        Self {
+            position,
+            intensity,
+            range: None,
+        }
+    }
+    pub fn with_range(mut self, range: Double) -> Self {
+        self.range = Some(range);
+        self
+    }
+}
+
+impl Light for PointLight {
+    fn sample_li(&self, point: Point3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.len_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let falloff = distance_falloff(distance_squared, self.range);
+        if falloff <= 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            irradiance: self.intensity * falloff,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inverse_square_falloff() {
+        let light = PointLight::new(Point3::new([0.0, 2.0, 0.0]), RGB::new([8.0, 4.0, 2.0]));
+        for (point, distance) in [([0.0, 0.0, 0.0], 2.0), ([0.0, 2.0, 4.0], 4.0)] {
+            let sample = light.sample_li(Point3::new(point)).unwrap();
+            assert!((sample.distance - distance).abs() < 1e-12);
+            assert!((sample.direction.len() - 1.0).abs() < 1e-12);
+            let expected = light.intensity / (distance * distance);
+            assert_eq!(sample.irradiance.0, expected.0);
+        }
+        let sample = light.sample_li(Point3::default()).unwrap();
+        assert_eq!(sample.direction.0, [0.0, 1.0, 0.0]);
+        assert!(light.sample_li(light.position).is_none());
+    }
+
+    #[test]
+    fn range_fades_the_light_out() {
+        let light = PointLight::new(Point3::default(), RGB::new([1.0; 3])).with_range(4.0);
+        let irradiance = |distance: Double| {
+            light
+                .sample_li(Point3::new([distance, 0.0, 0.0]))
+                .map_or(0.0, |sample| sample.irradiance.r())
+        };
+        // 1 - (d/r)^4 over the square of the distance
+        assert!((irradiance(2.0) - (1.0 - 1.0 / 16.0) / 4.0).abs() < 1e-12);
+        assert!((irradiance(0.1) - 100.0).abs() < 1e-3);
+        assert_eq!(irradiance(4.0), 0.0);
+        assert_eq!(irradiance(5.0), 0.0);
+        let samples: Vec<Double> = (1..40).map(|i| irradiance(i as Double * 0.1)).collect();
+        assert!(samples.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+}
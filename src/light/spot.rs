@@ -0,0 +1,157 @@
+use crate::{
+    Double,
+    color::RGB,
+    vec3::{Point3, Vector3},
+};
+
+use super::{Light, LightSample, distance_falloff};
+
+/// Point light restricted to a cone, fading out between the inner and outer angles.
+pub struct SpotLight {
+    pub position: Point3,
+    /// Radiant intensity along the axis
+    pub intensity: RGB,
+    /// Distance at which the light has faded out, unlimited if `None`
+    pub range: Option<Double>,
+    /// Unit axis of the cone, the direction the light shines to
+    direction: Vector3,
+    /// Cosine of the angle from the axis up to which the intensity is full
+    cos_inner: Double,
+    /// Cosine of the angle from the axis beyond which there is no light
+    cos_outer: Double,
+}
+
+impl SpotLight {
+    /// Cone pointing from `position` to `look_at`, the angles are from the axis, in degrees.
+    pub fn new(
+        position: Point3,
+        look_at: Point3,
+        intensity: RGB,
+        inner_angle: Double,
+        outer_angle: Double,
+    ) -> Self {
+        Self::from_direction(
+            position,
+            look_at - position,
+            intensity,
+            inner_angle,
+            outer_angle,
+        )
+    }
+    pub fn from_direction(
+        position: Point3,
+        direction: Vector3,
+        intensity: RGB,
+        inner_angle: Double,
+        outer_angle: Double,
+    ) -> Self {
+        let outer_angle = outer_angle.clamp(0.0, 180.0);
+        let inner_angle = inner_angle.clamp(0.0, outer_angle);
+        Self {
+            position,
+            intensity,
+            range: None,
+            direction: direction.unit_vector(),
+            cos_inner: inner_angle.to_radians().cos(),
+            cos_outer: outer_angle.to_radians().cos(),
+        }
+    }
+    pub fn with_range(mut self, range: Double) -> Self {
+        self.range = Some(range);
+        self
+    }
+    /// Unit axis of the cone.
+    pub fn direction(&self) -> Vector3 {
+        self.direction
+    }
+    /// Fraction of the intensity shining along the unit vector `direction`.
+    fn falloff(&self, direction: Vector3) -> Double {
+        let cos_theta = self.direction.dot(direction);
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        // smoothstep between the two cones
+        let t = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_li(&self, point: Point3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance_squared = to_light.len_squared();
+        if distance_squared == 0.0 {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction) * distance_falloff(distance_squared, self.range);
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * falloff,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spot at the origin shining down -y, full up to 20 degrees, dark past 40.
+    fn spot() -> SpotLight {
+        SpotLight::from_direction(
+            Point3::default(),
+            Vector3::new([0.0, -3.0, 0.0]),
+            RGB::new([1.0; 3]),
+            20.0,
+            40.0,
+        )
+    }
+
+    /// Fraction of the intensity at `degrees` from the axis, one unit away.
+    fn fraction(light: &SpotLight, degrees: Double) -> Double {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        light
+            .sample_li(Point3::new([sin, -cos, 0.0]))
+            .map_or(0.0, |sample| sample.irradiance.r())
+    }
+
+    #[test]
+    fn smoothstep_between_the_cones() {
+        let light = spot();
+        assert_eq!(light.direction().0, [0.0, -1.0, 0.0]);
+        for degrees in [0.0, 10.0, 19.9] {
+            assert!((fraction(&light, degrees) - 1.0).abs() < 1e-12, "{degrees}");
+        }
+        for degrees in [40.1, 60.0, 90.0, 180.0] {
+            assert_eq!(fraction(&light, degrees), 0.0, "{degrees}");
+        }
+        let between: Vec<Double> = (0..=200)
+            .map(|i| fraction(&light, 20.0 + i as Double * 0.1))
+            .collect();
+        assert!(between.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(between.iter().all(|&f| (0.0..=1.0).contains(&f)));
+        // smoothstep is 1/2 half way between the cosines
+        let half = ((20.0_f64.to_radians().cos() + 40.0_f64.to_radians().cos()) / 2.0).acos();
+        assert!((fraction(&light, half.to_degrees()) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn inverse_square_falloff_along_the_axis() {
+        let light = spot().with_range(10.0);
+        let sample = light.sample_li(Point3::new([0.0, -2.0, 0.0])).unwrap();
+        assert_eq!(sample.direction.0, [0.0, 1.0, 0.0]);
+        let expected = (1.0 - (0.2 as Double).powi(4)) / 4.0;
+        assert!((sample.irradiance.r() - expected).abs() < 1e-12);
+        assert!(light.sample_li(Point3::new([0.0, -10.0, 0.0])).is_none());
+        // behind the light
+        assert!(light.sample_li(Point3::new([0.0, 2.0, 0.0])).is_none());
+    }
+}
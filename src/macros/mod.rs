@@ -11,6 +11,12 @@
 // #[macro_export] see https://doc.rust-lang.org/reference/macros-by-example.html#scoping-exporting-and-importing
 #[macro_export]
 /// ```
+/// # use raytracing_rs::vec3_op_vec3;
+/// # use std::ops::{Add, Div, Mul, Sub};
+/// # struct A([f64; 3]);
+/// # struct B([f64; 3]);
+/// # impl A { fn new(v: [f64; 3]) -> Self { Self(v) } }
+/// # impl B { fn new(v: [f64; 3]) -> Self { Self(v) } }
 ///vec3_op_vec3! {
 ///[Add,Sub,Mul,Div]
 ///[add,sub,mul,div]
@@ -21,6 +27,8 @@
 ///[add,sub,mul,div]
 ///A,B =>B
 ///}
+/// # assert_eq!((A([1.0, 2.0, 3.0]) + B([1.0; 3])).0, [2.0, 3.0, 4.0]);
+/// # assert_eq!((A([1.0, 2.0, 3.0]) * A([2.0; 3])).0, [2.0, 4.0, 6.0]);
 /// ```
 macro_rules! vec3_op_vec3 {
     (
@@ -48,12 +56,16 @@ macro_rules! vec3_op_vec3 {
 }
 #[macro_export]
 /// ```
+/// # use raytracing_rs::vec3_op_scalar;
+/// # use std::ops::{Add, Div, Mul, Sub};
 /// struct C([u32; 3]);
+/// # impl C { fn new(v: [u32; 3]) -> Self { Self(v) } }
 /// vec3_op_scalar! {
 /// [Add,Sub,Mul,Div]
 /// [add,sub,mul,div]
 /// C,u32 =>C
 /// }
+/// # assert_eq!((C([2, 4, 6]) / 2).0, [1, 2, 3]);
 /// ```
 macro_rules! vec3_op_scalar {
     (
@@ -80,6 +92,11 @@ macro_rules! vec3_op_scalar {
 }
 #[macro_export]
 /// ```
+/// # use raytracing_rs::vec3_op_vec3_and_op_assign;
+/// # #[derive(Clone, Copy)]
+/// # struct A([f64; 3]);
+/// # struct B([f64; 3]);
+/// # impl A { fn new(v: [f64; 3]) -> Self { Self(v) } }
 /// vec3_op_vec3_and_op_assign! {
 ///     [Add,Sub,Mul,Div]
 ///     [add,sub,mul,div]
@@ -87,6 +104,9 @@ macro_rules! vec3_op_scalar {
 ///     [add_assign,sub_assign,mul_assign,div_assign]
 ///     A,B =>A
 /// }
+/// # let mut a = A([1.0, 2.0, 3.0]);
+/// # a -= B([1.0; 3]);
+/// # assert_eq!(a.0, [0.0, 1.0, 2.0]);
 /// ```
 macro_rules! vec3_op_vec3_and_op_assign {
     (
@@ -124,6 +144,11 @@ macro_rules! vec3_op_vec3_and_op_assign {
 }
 #[macro_export]
 /// ```
+/// # use raytracing_rs::vec3_op_scalar_and_op_assign;
+/// # #[derive(Clone, Copy)]
+/// # struct A([f64; 3]);
+/// # type B = f64;
+/// # impl A { fn new(v: [f64; 3]) -> Self { Self(v) } }
 /// vec3_op_scalar_and_op_assign! {
 ///     [Add,Sub,Mul,Div]
 ///     [add,sub,mul,div]
@@ -131,6 +156,9 @@ macro_rules! vec3_op_vec3_and_op_assign {
 ///     [add_assign,sub_assign,mul_assign,div_assign]
 ///     A,B =>A
 /// }
+/// # let mut a = A([1.0, 2.0, 3.0]);
+/// # a *= 2.0;
+/// # assert_eq!(a.0, [2.0, 4.0, 6.0]);
 /// ```
 macro_rules! vec3_op_scalar_and_op_assign {
     (
@@ -168,11 +196,24 @@ macro_rules! vec3_op_scalar_and_op_assign {
 #[macro_export]
 /// # Example
 /// ```
+/// # #![feature(macro_metavar_expr)]
+/// # use raytracing_rs::gen_getter;
+/// # type N = f64;
 /// struct A([N; 3]);
 ///struct B([N; 3]);
+/// # impl std::ops::Index<usize> for A {
+/// #     type Output = N;
+/// #     fn index(&self, i: usize) -> &N { &self.0[i] }
+/// # }
+/// # impl std::ops::Index<usize> for B {
+/// #     type Output = N;
+/// #     fn index(&self, i: usize) -> &N { &self.0[i] }
+/// # }
 ///
 /// gen_getter! {A[x,y,z]=>N}
 /// gen_getter! {B[r,g,b]=>N}
+/// # assert_eq!(A([1.0, 2.0, 3.0]).z(), 3.0);
+/// # assert_eq!(B([1.0, 2.0, 3.0]).g(), 2.0);
 /// ```
 macro_rules! gen_getter {
     (
@@ -191,6 +232,16 @@ macro_rules! gen_getter {
 }
 #[macro_export]
 /// ```
+/// # #![feature(macro_metavar_expr)]
+/// # use raytracing_rs::{Double, gen_builder_lite};
+/// # struct Vector3([Double; 3]);
+/// # impl std::ops::Index<usize> for Vector3 {
+/// #     type Output = Double;
+/// #     fn index(&self, i: usize) -> &Double { &self.0[i] }
+/// # }
+/// # impl std::ops::IndexMut<usize> for Vector3 {
+/// #     fn index_mut(&mut self, i: usize) -> &mut Double { &mut self.0[i] }
+/// # }
 /// gen_builder_lite! {
 /// Vector3
 /// [
@@ -199,6 +250,7 @@ macro_rules! gen_getter {
 ///     with_z
 /// ]
 /// }
+/// # assert_eq!(Vector3([0.0; 3]).with_y(2.0).0, [0.0, 2.0, 0.0]);
 /// ```
 /// https://matklad.github.io/2022/05/29/builder-lite.html
 macro_rules! gen_builder_lite {
@@ -1,17 +1,59 @@
+use std::io;
+
 use raytracing_rs::camera::Camera;
+use raytracing_rs::color::RGB;
+use raytracing_rs::filter::Filter;
 use raytracing_rs::hittable::HittableList;
+use raytracing_rs::hittable::bvh::BvhNode;
+use raytracing_rs::hittable::plane::Plane;
 use raytracing_rs::hittable::sphere::Sphere;
-use raytracing_rs::vec3::Point3;
-fn main() {
+use raytracing_rs::integrator::path::PathTracer;
+use raytracing_rs::material::dielectric::Dielectric;
+use raytracing_rs::material::lambertian::Lambertian;
+use raytracing_rs::material::metal::Metal;
+use raytracing_rs::vec3::{Point3, Vector3};
+fn main() -> io::Result<()> {
     //world
-    let sphere_small = Sphere::from_point3(Point3::default().with_z(-1.0)).with_radius(0.5);
-    let sphere_large = Sphere::from_array([0.0,-100.5,-1.0]).with_radius(100.0);
-    let world = HittableList::from(vec![
-        sphere_small.into(),
-        sphere_large.into(),
+    let material_ground = Lambertian::new(RGB::new([0.8, 0.8, 0.0]));
+    let material_center = Lambertian::new(RGB::new([0.1, 0.2, 0.5]));
+    let material_left = Dielectric::new(1.5);
+    let material_right = Metal::new(RGB::new([0.8, 0.6, 0.2]), 0.3);
+
+    let sphere_center = Sphere::from_point3(Point3::default().with_z(-1.2))
+        .with_radius(0.5)
+        .with_material(material_center);
+    let sphere_left = Sphere::from_array([-1.0, 0.0, -1.0])
+        .with_radius(0.5)
+        .with_material(material_left);
+    let sphere_right = Sphere::from_array([1.0, 0.0, -1.0])
+        .with_radius(0.5)
+        .with_material(material_right);
+    let ground = Plane::new(
+        Point3::new([0.0, -0.5, 0.0]),
+        Vector3::new([0.0, 1.0, 0.0]),
+    )
+    .with_material(material_ground);
+    let spheres = HittableList::from(vec![
+        sphere_center.into(),
+        sphere_left.into(),
+        sphere_right.into(),
     ]);
+    let mut world = HittableList::new();
+    world.push(BvhNode::from(spheres));
+    // the plane is unbounded, so it stays out of the BVH
+    world.push(ground);
     let image_width = 400;
     let aspect_ratio = 16.0 / 9.0;
-    let camera = Camera::new(aspect_ratio, image_width);
-    camera.render(world);
+    let camera = Camera::new(aspect_ratio, image_width)
+        .with_samples_per_pixel(100)
+        .with_filter(Filter::mitchell())
+        .with_integrator(PathTracer::new(50))
+        .with_vfov(20.0)
+        .with_look_from(Point3::new([-2.0, 2.0, 1.0]))
+        .with_look_at(Point3::new([0.0, 0.0, -1.0]))
+        .with_vup(Vector3::new([0.0, 1.0, 0.0]))
+        .with_defocus_angle(10.0)
+        .with_focus_dist(3.4);
+    let image = camera.render(world);
+    image.save("img.png")
 }
@@ -0,0 +1,125 @@
+use crate::{Double, color::RGB, hittable::HitRecord, ray::Ray, sampler::Sampler};
+
+use super::Material;
+
+/// Clear material such as glass or water, which always refracts or reflects.
+pub struct Dielectric {
+    /// Refractive index in vacuum or air, or the ratio of the material's
+    /// refractive index over the refractive index of the enclosing media
+    pub refraction_index: Double,
+}
+
+impl Dielectric {
+    pub fn new(refraction_index: Double) -> Self {
+        Self { refraction_index }
+    }
+    /// Schlick's approximation for reflectance.
+    fn reflectance(cosine: Double, refraction_index: Double) -> Double {
+        let r0 = ((1.0 - refraction_index) / (1.0 + refraction_index)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(RGB, Ray)> {
+        let attenuation = RGB::new([1.0; 3]);
+        let ri = if record.normal_direction.is_outward() {
+            1.0 / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+        let unit_direction = ray_in.direction.unit_vector();
+        let cos_theta = (-unit_direction).dot(record.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        // total internal reflection when Snell's law has no solution
+        let cannot_refract = ri * sin_theta > 1.0;
+        let direction = if cannot_refract || Self::reflectance(cos_theta, ri) > sampler.get_1d() {
+            unit_direction.reflect(record.normal)
+        } else {
+            unit_direction.refract(record.normal, ri)
+        };
+        Some((attenuation, Ray::new(record.point, direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sampler::independent::IndependentSampler,
+        vec3::{Point3, Vector3},
+    };
+
+    const GLASS: Double = 1.5;
+
+    /// Ray hitting the xz plane at the origin at `degrees` from its normal +Y,
+    /// from above, or from below when `inside`.
+    fn hit(glass: &Dielectric, degrees: Double, inside: bool) -> (Ray, HitRecord<'_>) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let direction = Vector3::new([sin, if inside { cos } else { -cos }, 0.0]);
+        let ray = Ray::new(Point3::default() + (-direction), direction);
+        let normal = Vector3::new([0.0, 1.0, 0.0]);
+        let record = HitRecord::new(&ray, 1.0, Point3::default(), normal, glass);
+        (ray, record)
+    }
+
+    /// Fraction of the scattered rays which are reflected back to the side they came from.
+    fn reflected_fraction(degrees: Double, inside: bool) -> Double {
+        let glass = Dielectric::new(GLASS);
+        let (ray, record) = hit(&glass, degrees, inside);
+        let mut sampler = IndependentSampler::new(2);
+        let count = 100_000;
+        let reflected = (0..count)
+            .filter(|_| {
+                let (attenuation, scattered) = glass.scatter(&ray, &record, &mut sampler).unwrap();
+                assert_eq!(attenuation.0, [1.0; 3]);
+                scattered.direction.dot(record.normal) > 0.0
+            })
+            .count();
+        reflected as Double / count as Double
+    }
+
+    #[test]
+    fn reflects_with_the_schlick_probability() {
+        // (1 - 1.5)^2 / (1 + 1.5)^2 at normal incidence, 1 at grazing
+        assert!((Dielectric::reflectance(1.0, GLASS) - 0.04).abs() < 1e-12);
+        assert!((Dielectric::reflectance(0.0, GLASS) - 1.0).abs() < 1e-12);
+        for degrees in [0.0, 45.0, 80.0_f64] {
+            let expected = Dielectric::reflectance(degrees.to_radians().cos(), 1.0 / GLASS);
+            let fraction = reflected_fraction(degrees, false);
+            assert!(
+                (fraction - expected).abs() < 0.005,
+                "{fraction} at {degrees}°, {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn refraction_follows_snell() {
+        let glass = Dielectric::new(GLASS);
+        let (ray, record) = hit(&glass, 30.0, false);
+        let mut sampler = IndependentSampler::new(5);
+        let refracted = (0..100)
+            .map(|_| glass.scatter(&ray, &record, &mut sampler).unwrap().1)
+            .find(|scattered| scattered.direction.y() < 0.0)
+            .unwrap();
+        let direction = refracted.direction.unit_vector();
+        // sin θ' = sin θ / η
+        assert!((direction.x() - 0.5 / GLASS).abs() < 1e-12);
+        assert_eq!(refracted.origin, record.point);
+    }
+
+    #[test]
+    fn total_internal_reflection() {
+        // the critical angle of glass is asin(1 / 1.5) ≈ 41.8°
+        assert_eq!(reflected_fraction(45.0, true), 1.0);
+        assert_eq!(reflected_fraction(80.0, true), 1.0);
+        // below it some light leaves the glass
+        assert!(reflected_fraction(30.0, true) < 0.5);
+    }
+}
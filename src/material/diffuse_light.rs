@@ -0,0 +1,41 @@
+use crate::{color::RGB, hittable::HitRecord, ray::Ray, sampler::Sampler};
+
+use super::Material;
+
+/// Surface emitting the same radiance in every direction, it reflects no light.
+pub struct DiffuseLight {
+    pub emit: RGB,
+    /// Emit from the back side too, otherwise only the side the outward normal points to glows
+    pub two_sided: bool,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: RGB) -> Self {
+        Self {
+            emit,
+            two_sided: false,
+        }
+    }
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(RGB, Ray)> {
+        None
+    }
+    fn emitted(&self, _ray_in: &Ray, record: &HitRecord) -> RGB {
+        if self.two_sided || record.normal_direction.is_outward() {
+            self.emit
+        } else {
+            RGB::default()
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::f64::consts::PI;
+
+use crate::{Double, color::RGB, hittable::HitRecord, ray::Ray, sampler::Sampler, vec3::Vector3};
+
+use super::Material;
+
+/// Ideal diffuse surface.
+///
+/// The vertex color of a mesh, if any, tints the albedo.
+pub struct Lambertian {
+    pub albedo: RGB,
+}
+
+impl Lambertian {
+    pub fn new(albedo: RGB) -> Self {
+        Self { albedo }
+    }
+    fn albedo_at(&self, record: &HitRecord) -> RGB {
+        match record.color {
+            Some(color) => self.albedo * color,
+            None => self.albedo,
+        }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(RGB, Ray)> {
+        // normal + random unit vector is cosine distributed around the normal
+        let mut scatter_direction = record.normal + Vector3::random_unit(sampler);
+        // catch degenerate scatter direction
+        if scatter_direction.near_zero() {
+            scatter_direction = record.normal;
+        }
+        let scattered = Ray::new(record.point, scatter_direction);
+        Some((self.albedo_at(record), scattered))
+    }
+    fn scattering(&self, ray_in: &Ray, record: &HitRecord, scattered: &Ray) -> RGB {
+        // f = albedo / π
+        self.albedo_at(record) * self.scattering_pdf(ray_in, record, scattered)
+    }
+    fn scattering_pdf(&self, _ray_in: &Ray, record: &HitRecord, scattered: &Ray) -> Double {
+        // cosine distribution, cos θ / π
+        let cosine = record.normal.dot(scattered.direction.unit_vector());
+        cosine.max(0.0) / PI
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{Double, color::RGB, hittable::HitRecord, ray::Ray, sampler::Sampler, vec3::Vector3};
+
+use super::Material;
+
+/// Mirror-like surface, `fuzz` perturbs the reflected direction.
+pub struct Metal {
+    pub albedo: RGB,
+    pub fuzz: Double,
+}
+
+impl Metal {
+    pub fn new(albedo: RGB, fuzz: Double) -> Self {
+        Self {
+            albedo,
+            fuzz: fuzz.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Material for Metal {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(RGB, Ray)> {
+        let reflected = ray_in.direction.reflect(record.normal).unit_vector()
+            + Vector3::random_unit(sampler) * self.fuzz;
+        let scattered = Ray::new(record.point, reflected);
+        // fuzz may push the ray below the surface, absorb it then
+        (scattered.direction.dot(record.normal) > 0.0).then_some((self.albedo, scattered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sampler::independent::IndependentSampler, vec3::Point3};
+
+    /// A ray hitting the xz plane at the origin at `degrees` from its normal +Y.
+    fn incoming(degrees: Double) -> Ray {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let direction = Vector3::new([sin, -cos, 0.0]);
+        Ray::new(Point3::default() + (-direction), direction)
+    }
+
+    #[test]
+    fn mirror_without_fuzz() {
+        let metal = Metal::new(RGB::new([0.9, 0.6, 0.3]), 0.0);
+        let ray = incoming(30.0);
+        let normal = Vector3::new([0.0, 1.0, 0.0]);
+        let record = HitRecord::new(&ray, 1.0, Point3::default(), normal, &metal);
+        let mut sampler = IndependentSampler::new(1);
+        let (attenuation, scattered) = metal.scatter(&ray, &record, &mut sampler).unwrap();
+        assert_eq!(attenuation, metal.albedo);
+        let expected = Vector3::new([0.5, 0.75_f64.sqrt(), 0.0]);
+        assert!((scattered.direction - expected).len() < 1e-12);
+    }
+
+    #[test]
+    fn fuzz_stays_above_the_surface() {
+        let metal = Metal::new(RGB::new([0.8; 3]), 1.0);
+        let normal = Vector3::new([0.0, 1.0, 0.0]);
+        let mut sampler = IndependentSampler::new(4);
+        for degrees in [0.0, 60.0, 89.0] {
+            let ray = incoming(degrees);
+            let record = HitRecord::new(&ray, 1.0, Point3::default(), normal, &metal);
+            let mut absorbed = 0;
+            for _ in 0..10_000 {
+                match metal.scatter(&ray, &record, &mut sampler) {
+                    Some((_, scattered)) => assert!(scattered.direction.dot(normal) > 0.0),
+                    None => absorbed += 1,
+                }
+            }
+            // near grazing, fuzz pushes about half of the rays below the surface
+            if degrees == 89.0 {
+                assert!(absorbed > 1_000, "{absorbed} absorbed at {degrees}°");
+            }
+        }
+    }
+
+    #[test]
+    fn fuzz_is_clamped() {
+        assert_eq!(Metal::new(RGB::default(), 3.0).fuzz, 1.0);
+        assert_eq!(Metal::new(RGB::default(), -1.0).fuzz, 0.0);
+    }
+}
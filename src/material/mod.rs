@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use crate::{Double, color::RGB, hittable::HitRecord, ray::Ray, sampler::Sampler};
+pub mod dielectric;
+pub mod diffuse_light;
+pub mod lambertian;
+pub mod metal;
+pub type SharedMaterial = Arc<dyn Material + 'static>;
+pub trait Material: Send + Sync {
+    /// Produce a scattered ray and how much it is attenuated,
+    /// or `None` if the incident ray is absorbed.
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(RGB, Ray)>;
+    /// BSDF times the cosine term, `f * |cos θ|`, for light leaving along `scattered`.
+    ///
+    /// Used to weigh samples which were not chosen by [`Material::scatter`], like
+    /// the directions towards the lights. Black for specular materials.
+    fn scattering(&self, _ray_in: &Ray, _record: &HitRecord, _scattered: &Ray) -> RGB {
+        RGB::default()
+    }
+    /// Density, by solid angle, of [`Material::scatter`] choosing the direction of `scattered`.
+    ///
+    /// Zero for specular materials, whose directions cannot be sampled any other way.
+    fn scattering_pdf(&self, _ray_in: &Ray, _record: &HitRecord, _scattered: &Ray) -> Double {
+        0.0
+    }
+    /// Radiance the surface emits towards the origin of `ray_in`, black unless it is a light.
+    fn emitted(&self, _ray_in: &Ray, _record: &HitRecord) -> RGB {
+        RGB::default()
+    }
+}
+
+/// Grey diffuse material of objects built without one.
+pub(crate) fn default_material() -> SharedMaterial {
+    Arc::new(lambertian::Lambertian::new(RGB::new([0.5; 3])))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        Double,
+        sampler::independent::IndependentSampler,
+        vec3::{Point3, Vector3},
+    };
+
+    /// Tell the material types apart by how they behave on a surface facing +Y:
+    /// `"light"`, `"diffuse"`, `"glass"` or `"metal"`.
+    pub(crate) fn classify(material: &dyn Material) -> &'static str {
+        let ray = Ray::new(
+            Point3::new([-1.0, 1.0, 0.0]),
+            Vector3::new([1.0, -1.0, 0.0]),
+        );
+        let normal = Vector3::new([0.0, 1.0, 0.0]);
+        let record = HitRecord::new(&ray, 1.0, Point3::default(), normal, material);
+        let emitted = material.emitted(&ray, &record);
+        if emitted.r().max(emitted.g()).max(emitted.b()) > 0.0 {
+            return "light";
+        }
+        let mut sampler = IndependentSampler::new(1);
+        let directions: Vec<Vector3> = (0..64)
+            .filter_map(|_| material.scatter(&ray, &record, &mut sampler))
+            .map(|(_, scattered)| scattered.direction.unit_vector())
+            .collect();
+        if directions.iter().any(|direction| direction.y() < 0.0) {
+            return "glass";
+        }
+        // a diffuse bounce is centered on the normal, a reflection leans forward
+        let forward = directions
+            .iter()
+            .map(|direction| direction.x())
+            .sum::<Double>();
+        if forward > 0.25 * directions.len() as Double {
+            "metal"
+        } else {
+            "diffuse"
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Double, random::Rng, random::mix};
+
+use super::{ONE_MINUS_EPSILON, Sampler, dimension_hash, permutation_element};
+
+/// Bases of the Halton dimensions, dimensions past the table fall back to pseudo-random values.
+const PRIMES: [u64; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311,
+];
+
+/// Halton sequence, the radical inverse of the sample index in a different prime base
+/// per dimension, with the digits of every pixel and dimension Owen scrambled.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel_index: u64,
+    sample_index: u64,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            pixel_index: 0,
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+    fn sample_dimension(&mut self) -> Double {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        match PRIMES.get(dimension as usize) {
+            Some(&base) => {
+                let hash = dimension_hash(self.seed, self.pixel_index, dimension);
+                owen_scrambled_radical_inverse(base, self.sample_index, hash)
+            }
+            None => self.rng.random_double(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, pixel_index: u64, sample_index: u64) {
+        self.pixel_index = pixel_index;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::for_sample(self.seed, pixel_index, sample_index);
+    }
+    fn get_1d(&mut self) -> Double {
+        self.sample_dimension()
+    }
+    fn get_2d(&mut self) -> [Double; 2] {
+        [self.sample_dimension(), self.sample_dimension()]
+    }
+}
+
+/// Mirror the base `base` digits of `index` around the radix point, permuting each digit
+/// with a permutation chosen by the digits before it, so the scrambling is nested (Owen).
+fn owen_scrambled_radical_inverse(base: u64, mut index: u64, hash: u64) -> Double {
+    let inverse_base = 1.0 / base as Double;
+    // digits seen so far, only used to key the permutations: past 64 bits it
+    // wraps, long after the digits stopped changing the double
+    let mut prefix: u64 = 0;
+    let mut inverse_base_m = 1.0;
+    let mut value = 0.0;
+    // stop once further digits no longer change the double
+    while 1.0 - (base - 1) as Double * inverse_base_m < 1.0 {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_hash = mix(hash ^ prefix);
+        let digit = permutation_element(digit as u32, base as u32, digit_hash as u32) as u64;
+        prefix = prefix.wrapping_mul(base).wrapping_add(digit);
+        inverse_base_m *= inverse_base;
+        value += digit as Double * inverse_base_m;
+        index = next;
+    }
+    value.min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(count: u64, dimensions: usize) -> Vec<Vec<Double>> {
+        let mut sampler = HaltonSampler::new(7);
+        (0..count)
+            .map(|index| {
+                sampler.start_pixel_sample(42, index);
+                (0..dimensions).map(|_| sampler.get_1d()).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_dimension_is_stratified() {
+        for log_count in 0..10 {
+            let count = 1 << log_count;
+            let mut cells = vec![0; count];
+            for point in points(count as u64, 1) {
+                cells[(point[0] * count as Double) as usize] += 1;
+            }
+            assert!(cells.iter().all(|&n| n == 1), "{count} points: {cells:?}");
+        }
+    }
+
+    #[test]
+    fn elementary_intervals_hold_one_point() {
+        // the first 2^a 3^b points put one point in every 2^-a by 3^-b box
+        for a in 0..5 {
+            for b in 0..4 {
+                let (columns, rows) = (1usize << a, 3usize.pow(b));
+                let count = columns * rows;
+                let mut cells = vec![0; count];
+                for point in points(count as u64, 2) {
+                    let x = (point[0] * columns as Double) as usize;
+                    let y = (point[1] * rows as Double) as usize;
+                    cells[y * columns + x] += 1;
+                }
+                assert!(cells.iter().all(|&n| n == 1), "2^{a} 3^{b}: {cells:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn large_bases_stay_in_unit_interval() {
+        for &base in PRIMES.iter().rev().take(4) {
+            for index in [0, 1, 1000, u64::MAX] {
+                let value = owen_scrambled_radical_inverse(base, index, 3);
+                assert!((0.0..1.0).contains(&value), "base {base}: {value}");
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Double, random::Rng};
+
+use super::Sampler;
+
+/// Uncorrelated pseudo-random samples.
+pub struct IndependentSampler {
+    seed: u64,
+    rng: Rng,
+}
+
+impl IndependentSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, pixel_index: u64, sample_index: u64) {
+        self.rng = Rng::for_sample(self.seed, pixel_index, sample_index);
+    }
+    fn get_1d(&mut self) -> Double {
+        self.rng.random_double()
+    }
+    fn get_2d(&mut self) -> [Double; 2] {
+        [self.rng.random_double(), self.rng.random_double()]
+    }
+}
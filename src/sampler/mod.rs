@@ -0,0 +1,144 @@
+use crate::{Double, random::mix};
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+/// Largest double below one, samples are kept in [0,1).
+pub const ONE_MINUS_EPSILON: Double = 1.0 - Double::EPSILON / 2.0;
+
+/// Source of the sample values driving the camera, lens and BSDF.
+///
+/// Each call to `get_1d`/`get_2d` consumes the next dimension(s) of the
+/// current sample. Consumers must request dimensions in the same order for
+/// every sample, so that the same dimension always drives the same decision.
+pub trait Sampler {
+    /// Start sample `sample_index` of the pixel `pixel_index`, rewinding to the first dimension.
+    fn start_pixel_sample(&mut self, pixel_index: u64, sample_index: u64);
+    /// Next sample value in [0,1).
+    fn get_1d(&mut self) -> Double;
+    /// Next two sample values in [0,1), well distributed as a pair.
+    fn get_2d(&mut self) -> [Double; 2];
+}
+
+/// Which [`Sampler`] the camera creates for its worker threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SamplerKind {
+    /// Plain pseudo-random numbers
+    #[default]
+    Independent,
+    /// Jittered strata, one per sample of a pixel
+    Stratified,
+    /// Owen scrambled Halton sequence
+    Halton,
+    /// (0,2)-sequence with Owen scrambling, padded over the dimensions
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn create(&self, seed: u64, samples_per_pixel: u32) -> Box<dyn Sampler> {
+        match self {
+            SamplerKind::Independent => Box::new(independent::IndependentSampler::new(seed)),
+            SamplerKind::Stratified => {
+                Box::new(stratified::StratifiedSampler::new(seed, samples_per_pixel))
+            }
+            SamplerKind::Halton => Box::new(halton::HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(sobol::SobolSampler::new(seed, samples_per_pixel)),
+        }
+    }
+}
+
+/// Hash of the seed, pixel and dimension, keying the per-dimension randomization.
+pub(crate) fn dimension_hash(seed: u64, pixel_index: u64, dimension: u32) -> u64 {
+    mix(seed ^ mix(pixel_index ^ mix(dimension as u64)))
+}
+
+/// Element `index` of a pseudo-random permutation of `0..n` picked by `seed`,
+/// after Andrew Kensler's "Correlated Multi-Jittered Sampling".
+pub(crate) fn permutation_element(mut index: u32, n: u32, seed: u32) -> u32 {
+    let mut w = n.wrapping_sub(1);
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    // cycle walking: repeat the bijection on [0, w] until the value lands below n
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170893d);
+        index ^= seed >> 16;
+        index ^= (index & w) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929eb3f);
+        index ^= seed >> 23;
+        index ^= (index & w) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935fa69);
+        index ^= (index & w) >> 11;
+        index = index.wrapping_mul(0x74dcb303);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0x9e501cc3);
+        index ^= (index & w) >> 2;
+        index = index.wrapping_mul(0xc860a3df);
+        index &= w;
+        index ^= index >> 5;
+        if index < n {
+            break;
+        }
+    }
+    index.wrapping_add(seed) % n
+}
+
+/// Hash based Owen scrambling of a 32 bit fixed point sample: each bit is
+/// flipped depending on the bits above it (Laine and Karras).
+pub(crate) fn owen_scramble(mut v: u32, seed: u32) -> u32 {
+    v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    v.reverse_bits()
+}
+
+/// Interpret `v` as a fixed point fraction in [0,1).
+pub(crate) fn to_unit(v: u32) -> Double {
+    (v as Double * (1.0 / 4294967296.0)).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(sampler: &mut dyn Sampler, pixel_index: u64, sample_index: u64) -> Vec<Double> {
+        sampler.start_pixel_sample(pixel_index, sample_index);
+        let mut values = Vec::new();
+        for _ in 0..40 {
+            values.push(sampler.get_1d());
+            values.extend(sampler.get_2d());
+        }
+        values
+    }
+
+    #[test]
+    fn restarting_a_sample_reproduces_it() {
+        let kinds = [
+            SamplerKind::Independent,
+            SamplerKind::Stratified,
+            SamplerKind::Halton,
+            SamplerKind::Sobol,
+        ];
+        for kind in kinds {
+            let mut sampler = kind.create(9, 16);
+            let first = sequence(sampler.as_mut(), 123, 5);
+            // other pixels and samples in between do not matter
+            sequence(sampler.as_mut(), 7, 3);
+            sequence(sampler.as_mut(), 123, 6);
+            assert_eq!(first, sequence(sampler.as_mut(), 123, 5), "{kind:?}");
+            // nor does the sampler instance
+            let mut other = kind.create(9, 16);
+            assert_eq!(first, sequence(other.as_mut(), 123, 5), "{kind:?}");
+            assert!(first.iter().all(|value| (0.0..1.0).contains(value)));
+        }
+    }
+}
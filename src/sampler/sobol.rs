@@ -0,0 +1,118 @@
+use crate::Double;
+
+use super::{Sampler, dimension_hash, owen_scramble, permutation_element, to_unit};
+
+/// Generator matrix of the second Sobol dimension, the first is the identity.
+const SOBOL_MATRIX_1: [u32; 32] = sobol_matrix_1();
+
+const fn sobol_matrix_1() -> [u32; 32] {
+    // primitive polynomial x + 1, every direction number m_k = 1
+    let mut matrix = [0u32; 32];
+    matrix[0] = 1 << 31;
+    let mut k = 1;
+    while k < 32 {
+        matrix[k] = matrix[k - 1] ^ (matrix[k - 1] >> 1);
+        k += 1;
+    }
+    matrix
+}
+
+/// Padded (0,2)-sequence: every pair of dimensions gets the first two Sobol
+/// dimensions, decorrelated from the other pairs by shuffling the sample
+/// order and Owen scrambling them with a different seed.
+pub struct SobolSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    pixel_index: u64,
+    sample_index: u64,
+    dimension: u32,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        Self {
+            seed,
+            samples_per_pixel: samples_per_pixel.max(1),
+            pixel_index: 0,
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+    /// Position of this sample in the shuffled sequence and the scrambling seed of the next dimension.
+    fn next_dimension(&mut self) -> (u32, u64) {
+        let hash = dimension_hash(self.seed, self.pixel_index, self.dimension);
+        self.dimension += 1;
+        let index = if (self.sample_index as u32) < self.samples_per_pixel {
+            permutation_element(
+                self.sample_index as u32,
+                self.samples_per_pixel,
+                hash as u32,
+            )
+        } else {
+            // more samples than announced: keep the order, the points stay well distributed
+            self.sample_index as u32
+        };
+        (index, hash)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, pixel_index: u64, sample_index: u64) {
+        self.pixel_index = pixel_index;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+    fn get_1d(&mut self) -> Double {
+        let (index, hash) = self.next_dimension();
+        to_unit(owen_scramble(index.reverse_bits(), hash as u32))
+    }
+    fn get_2d(&mut self) -> [Double; 2] {
+        let (index, hash) = self.next_dimension();
+        let x = index.reverse_bits();
+        let y = (0..32)
+            .filter(|bit| index & (1 << bit) != 0)
+            .fold(0, |acc, bit| acc ^ SOBOL_MATRIX_1[bit]);
+        [
+            to_unit(owen_scramble(x, hash as u32)),
+            to_unit(owen_scramble(y, (hash >> 32) as u32)),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_are_02_nets() {
+        for log_count in 0..9 {
+            let count = 1u32 << log_count;
+            let mut sampler = SobolSampler::new(11, count);
+            // three padded pairs of dimensions
+            let mut points = vec![Vec::new(); 3];
+            for index in 0..count {
+                sampler.start_pixel_sample(5, index as u64);
+                for pair in &mut points {
+                    pair.push(sampler.get_2d());
+                }
+            }
+            // every elementary interval of area 1/count holds exactly one point
+            for pair in &points {
+                for log_columns in 0..=log_count {
+                    let columns = 1usize << log_columns;
+                    let rows = count as usize / columns;
+                    let mut cells = vec![0; count as usize];
+                    for [x, y] in pair {
+                        let cell = (y * rows as Double) as usize * columns
+                            + (x * columns as Double) as usize;
+                        cells[cell] += 1;
+                    }
+                    assert!(
+                        cells.iter().all(|&n| n == 1),
+                        "{count} points in {columns}x{rows}: {cells:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{Double, random::Rng};
+
+use super::{ONE_MINUS_EPSILON, Sampler, dimension_hash, permutation_element};
+
+/// Splits every dimension into one stratum per sample and jitters inside it.
+///
+/// The strata are shuffled independently for each dimension and pixel,
+/// so dimensions are not correlated with each other.
+pub struct StratifiedSampler {
+    seed: u64,
+    samples_per_pixel: u32,
+    /// Strata along x and y of the 2D dimensions
+    strata: [u32; 2],
+    pixel_index: u64,
+    sample_index: u64,
+    dimension: u32,
+    rng: Rng,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        let samples_per_pixel = samples_per_pixel.max(1);
+        // close to square strata, with at least one stratum per sample
+        let x = ((samples_per_pixel as Double).sqrt().round() as u32).max(1);
+        let y = samples_per_pixel.div_ceil(x);
+        Self {
+            seed,
+            samples_per_pixel,
+            strata: [x, y],
+            pixel_index: 0,
+            sample_index: 0,
+            dimension: 0,
+            rng: Rng::new(seed),
+        }
+    }
+    /// The stratum this sample occupies among `n` strata of the current dimension.
+    fn stratum(&mut self, n: u32) -> u32 {
+        let hash = dimension_hash(self.seed, self.pixel_index, self.dimension);
+        self.dimension += 1;
+        permutation_element(self.sample_index as u32 % n, n, hash as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, pixel_index: u64, sample_index: u64) {
+        self.pixel_index = pixel_index;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Rng::for_sample(self.seed, pixel_index, sample_index);
+    }
+    fn get_1d(&mut self) -> Double {
+        let n = self.samples_per_pixel;
+        let stratum = self.stratum(n);
+        let jitter = self.rng.random_double();
+        ((stratum as Double + jitter) / n as Double).min(ONE_MINUS_EPSILON)
+    }
+    fn get_2d(&mut self) -> [Double; 2] {
+        let [x, y] = self.strata;
+        let stratum = self.stratum(x * y);
+        let (sx, sy) = (stratum % x, stratum / x);
+        let [jx, jy] = [self.rng.random_double(), self.rng.random_double()];
+        [
+            ((sx as Double + jx) / x as Double).min(ONE_MINUS_EPSILON),
+            ((sy as Double + jy) / y as Double).min(ONE_MINUS_EPSILON),
+        ]
+    }
+}
@@ -1,5 +1,6 @@
 // https://gabrielgambetta.com/computer-graphics-from-scratch/A0-linear-algebra.html
 use crate::Double;
+use crate::sampler::Sampler;
 use crate::{Array3, gen_builder_lite, gen_getter, vec3_op_scalar, vec3_op_vec3};
 
 use std::marker::PhantomData;
@@ -85,6 +86,63 @@ impl Vector3 {
     pub fn len_squared(&self) -> Double {
         self.dot(*self)
     }
+    /// Return true if the vector is close to zero in all dimensions.
+    pub fn near_zero(&self) -> bool {
+        let s = 1e-8;
+        self.0.iter().all(|n| n.abs() < s)
+    }
+    pub fn random_range(sampler: &mut dyn Sampler, min: Double, max: Double) -> Self {
+        Self::new([(); 3].map(|_| min + (max - min) * sampler.get_1d()))
+    }
+    /// Uniformly distributed unit vector, mapped from one 2D sample.
+    pub fn random_unit(sampler: &mut dyn Sampler) -> Self {
+        // z is uniform in [-1,1] on a sphere (Archimedes), the angle around z is uniform too
+        let [u, v] = sampler.get_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * v;
+        Self::new([r * phi.cos(), r * phi.sin(), z])
+    }
+    /// Uniformly distributed unit vector on the hemisphere around `normal`.
+    pub fn random_in_hemisphere(normal: Vector3, sampler: &mut dyn Sampler) -> Self {
+        let on_unit_sphere = Self::random_unit(sampler);
+        // flip it into the same hemisphere as the normal
+        if on_unit_sphere.dot(normal) > 0.0 {
+            on_unit_sphere
+        } else {
+            -on_unit_sphere
+        }
+    }
+    /// Uniformly distributed point inside the unit disk on the xy plane.
+    pub fn random_in_unit_disk(sampler: &mut dyn Sampler) -> Self {
+        // Shirley-Chiu concentric mapping keeps the strata of the square sample compact
+        let [u, v] = sampler.get_2d().map(|n| 2.0 * n - 1.0);
+        if u == 0.0 && v == 0.0 {
+            return Self::default();
+        }
+        let quarter_pi = std::f64::consts::FRAC_PI_4;
+        let (r, theta) = if u.abs() > v.abs() {
+            (u, quarter_pi * (v / u))
+        } else {
+            (v, 2.0 * quarter_pi - quarter_pi * (u / v))
+        };
+        Self::new([r * theta.cos(), r * theta.sin(), 0.0])
+    }
+    /// Mirror `self` about the surface normal `n`.
+    pub fn reflect(&self, n: Vector3) -> Self {
+        // V - 2*(V dot N)*N, the projection of V onto N is removed twice
+        *self - n * (2.0 * self.dot(n))
+    }
+    /// Refract the unit vector `self` through a surface with normal `n`,
+    /// `etai_over_etat` is the ratio of the refractive indices η/η'.
+    pub fn refract(&self, n: Vector3, etai_over_etat: Double) -> Self {
+        // Snell's law: η*sinθ = η'*sinθ'
+        // R'⊥ = η/η' * (R + cosθ*n), R'∥ = -sqrt(1 - |R'⊥|^2) * n
+        let cos_theta = (-*self).dot(n).min(1.0);
+        let r_out_perp = (*self + n * cos_theta) * etai_over_etat;
+        let r_out_parallel = n * -(1.0 - r_out_perp.len_squared()).abs().sqrt();
+        r_out_perp + r_out_parallel
+    }
 }
 
 // Point:P,Q;Vector:V